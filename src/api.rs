@@ -0,0 +1,780 @@
+use serde_json::{json, Value};
+use std::thread;
+use tiny_http::{Header, Method, Request, Response, Server};
+use ulid::Ulid;
+
+use crate::app::group::{self, SharedGroups};
+use crate::app::{SharedTableData, ValueData};
+
+const DEFAULT_API_PORT: u16 = 7878;
+
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
+#[serde(default)]
+pub struct ApiSettings {
+    pub enabled: bool,
+    pub port: u16,
+    /// Clients must send this as "Authorization: Bearer <token>".
+    pub token: String,
+    /// Let clients set what runs code or reaches files on this machine: on
+    /// change commands, browser arguments and profiles, scripts and steps.
+    pub allow_commands: bool,
+}
+
+impl Default for ApiSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: DEFAULT_API_PORT,
+            token: Ulid::new().to_string(),
+            allow_commands: false,
+        }
+    }
+}
+
+/// Body of create and update requests; fields left out are unchanged.
+#[derive(serde::Deserialize, schemars::JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct RowRequest {
+    name: Option<String>,
+    link: Option<String>,
+    css_selector: Option<String>,
+    mode: Option<crate::app::RowMode>,
+    fallback_selectors: Option<Vec<String>>,
+    /// `extra_args` and `profile_dir` are only accepted when commands are
+    /// allowed in the API settings.
+    browser: Option<crate::browser::BrowserOverrides>,
+    /// `login_steps` are only accepted when commands are allowed.
+    session: Option<crate::session::SessionSettings>,
+    /// Only accepted when commands are allowed.
+    steps: Option<Vec<crate::steps::Step>>,
+    /// Only accepted when commands are allowed.
+    script: Option<String>,
+    fields: Option<Vec<crate::fields::Field>>,
+    /// Only accepted when commands are allowed.
+    on_change_command: Option<String>,
+    group: Option<String>,
+    tags: Option<Vec<String>>,
+    enabled: Option<bool>,
+    /// `null` clears it.
+    #[serde(default, deserialize_with = "present")]
+    #[schemars(with = "Option<chrono::DateTime<chrono::Utc>>")]
+    paused_until: Option<Option<chrono::DateTime<chrono::Utc>>>,
+}
+
+/// Tells a field sent as `null` apart from one left out.
+fn present<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: serde::Deserialize<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+/// Serve the JSON API on localhost, reading and writing the same rows as the UI.
+///
+/// Routes:
+/// - `GET /rows`, `POST /rows`
+/// - `GET /rows/{id}`, `PATCH /rows/{id}`, `DELETE /rows/{id}`
+/// - `GET /rows/{id}/history`
+/// - `POST /rows/{id}/check`, `POST /check` (skips paused rows and groups)
+/// - `GET /schema`: JSON schemas of rows and of create/update bodies
+///
+/// Rows are sent without their cookies and the text typed by login steps.
+pub fn start_api_server(
+    settings: ApiSettings,
+    table_data: SharedTableData,
+    groups: SharedGroups,
+    ctx: egui::Context,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let server = match Server::http(("127.0.0.1", settings.port)) {
+            Ok(server) => server,
+            Err(e) => {
+                eprintln!("Failed to start API server: {:?}", e);
+                return;
+            }
+        };
+        println!("API server listening on http://127.0.0.1:{}", settings.port);
+
+        for request in server.incoming_requests() {
+            let settings = settings.clone();
+            let table_data = table_data.clone();
+            let groups = groups.clone();
+            let ctx = ctx.clone();
+            // checks load pages, so don't let them hold up other requests
+            thread::spawn(move || handle_request(request, &settings, &table_data, &groups, &ctx));
+        }
+    })
+}
+
+fn handle_request(
+    mut request: Request,
+    settings: &ApiSettings,
+    table_data: &SharedTableData,
+    groups: &SharedGroups,
+    ctx: &egui::Context,
+) {
+    let (status, body) = if !is_authorized(&request, &settings.token) {
+        (401, error_json("missing or invalid token"))
+    } else {
+        let mut content = String::new();
+        match request.as_reader().read_to_string(&mut content) {
+            Ok(_) => route(
+                request.method(),
+                request.url(),
+                &content,
+                settings,
+                table_data,
+                groups,
+            ),
+            Err(e) => (400, error_json(&e.to_string())),
+        }
+    };
+
+    if *request.method() != Method::Get && (200..300).contains(&status) {
+        ctx.request_repaint();
+    }
+
+    let response = Response::from_string(body.to_string())
+        .with_status_code(status)
+        .with_header(Header::from_bytes("Content-Type", "application/json").unwrap());
+    if let Err(e) = request.respond(response) {
+        eprintln!("Failed to send API response: {:?}", e);
+    }
+}
+
+fn is_authorized(request: &Request, token: &str) -> bool {
+    let expected = format!("Bearer {}", token);
+    !token.is_empty()
+        && request
+            .headers()
+            .iter()
+            .any(|header| header.field.equiv("Authorization") && header.value == *expected)
+}
+
+fn route(
+    method: &Method,
+    url: &str,
+    body: &str,
+    settings: &ApiSettings,
+    table_data: &SharedTableData,
+    groups: &SharedGroups,
+) -> (u16, Value) {
+    let path = url.split('?').next().unwrap_or_default();
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+
+    match (method, segments.as_slice()) {
+        (Method::Get, ["rows"]) => (200, rows_json(&table_data.lock().unwrap())),
+        (Method::Post, ["rows"]) => create_row(body, settings, table_data),
+        (Method::Post, ["check"]) => check_all_rows(table_data, groups),
+        (Method::Get, ["rows", id]) => with_row(table_data, id, |row| (200, row_json(row))),
+        (Method::Patch, ["rows", id]) => update_row(body, id, settings, table_data),
+        (Method::Delete, ["rows", id]) => delete_row(id, table_data),
+        (Method::Get, ["rows", id, "history"]) => {
+            with_row(table_data, id, |row| (200, json!(row.history)))
+        }
+        (Method::Post, ["rows", id, "check"]) => check_row(id, table_data),
+        (Method::Get, ["schema"]) => (
+            200,
+            json!({
+                "row": schemars::schema_for!(ValueData),
+                "row_request": schemars::schema_for!(RowRequest),
+            }),
+        ),
+        _ => (404, error_json("not found")),
+    }
+}
+
+fn with_row(
+    table_data: &SharedTableData,
+    id: &str,
+    f: impl FnOnce(&mut ValueData) -> (u16, Value),
+) -> (u16, Value) {
+    let mut table_data = table_data.lock().unwrap();
+    match table_data.iter_mut().find(|row| row.id == id) {
+        Some(row) => f(row),
+        None => (404, error_json("row not found")),
+    }
+}
+
+fn create_row(body: &str, settings: &ApiSettings, table_data: &SharedTableData) -> (u16, Value) {
+    let request: RowRequest = match serde_json::from_str(body) {
+        Ok(request) => request,
+        Err(e) => return (400, error_json(&e.to_string())),
+    };
+    if let Some(error) = check_request(&request, settings) {
+        return error;
+    }
+    // computed rows don't load a page
+    let computed = matches!(request.mode, Some(crate::app::RowMode::Computed { .. }));
+    let optional = || computed.then(String::new);
+    let (Some(name), Some(link), Some(css_selector)) = (
+        request.name,
+        request.link.or_else(optional),
+        request.css_selector.or_else(optional),
+    ) else {
+        return (
+            400,
+            error_json("name, link and css_selector are required, only name for computed rows"),
+        );
+    };
+
+    let mut draft = ValueData::new(
+        name.clone(),
+        link.clone(),
+        css_selector.clone(),
+        String::new(),
+    );
+    draft.fallback_selectors = request.fallback_selectors.unwrap_or_default();
+    draft.mode = request.mode.unwrap_or_default();
+    draft.browser = request.browser.unwrap_or_default();
+    draft.session = request.session.unwrap_or_default();
+    draft.steps = request.steps.unwrap_or_default();
+    draft.script = request.script.unwrap_or_default();
+    draft.fields = request.fields.unwrap_or_default();
+    let result = if computed {
+        crate::compute_value(&table_data.lock().unwrap(), &draft)
+    } else {
+        fetch_value(&draft)
+    };
+    if result.value.is_empty() {
+        // no row will own the screenshot taken of the draft
+        if let Some(path) = &result.screenshot {
+            crate::screenshot::remove_draft(path);
+        }
+        let message = result
+            .error
+            .unwrap_or_else(|| "check Link or CSS Selector.".to_owned());
+        return (
+            422,
+            error_json(&format!("Error fetching value: {}", message)),
+        );
+    }
+
+    let mut row = ValueData::new(name, link, css_selector, result.value);
+    row.fallback_selectors = draft.fallback_selectors;
+    row.mode = draft.mode;
+    row.browser = draft.browser;
+    row.session = draft.session;
+    row.steps = draft.steps;
+    row.script = draft.script;
+    row.fields = draft.fields;
+    for (field, value) in row.fields.iter_mut().zip(result.fields) {
+        field.value = value;
+    }
+    if let Some(cookies) = result.cookies {
+        crate::session::merge_cookies(&mut row.session.cookies, cookies);
+    }
+    row.fingerprint = result.fingerprint;
+    row.matched_selector = result.matched_selector;
+    row.on_change_command = request.on_change_command.unwrap_or_default();
+    row.group = request.group.unwrap_or_default();
+    row.tags = request.tags.unwrap_or_default();
+    let response = row_json(&row);
+    table_data.lock().unwrap().push(row);
+    (201, response)
+}
+
+fn update_row(
+    body: &str,
+    id: &str,
+    settings: &ApiSettings,
+    table_data: &SharedTableData,
+) -> (u16, Value) {
+    let request: RowRequest = match serde_json::from_str(body) {
+        Ok(request) => request,
+        Err(e) => return (400, error_json(&e.to_string())),
+    };
+    if let Some(error) = check_request(&request, settings) {
+        return error;
+    }
+    with_row(table_data, id, |row| {
+        if let Some(name) = request.name {
+            row.name = name;
+        }
+        if let Some(link) = request.link {
+            row.link = link;
+        }
+        if let Some(css_selector) = request.css_selector {
+            row.css_selector = css_selector;
+        }
+        if let Some(fallback_selectors) = request.fallback_selectors {
+            row.fallback_selectors = fallback_selectors;
+        }
+        if let Some(mode) = request.mode {
+            row.mode = mode;
+        }
+        if let Some(browser) = request.browser {
+            row.browser = browser;
+        }
+        if let Some(session) = request.session {
+            row.session = session;
+        }
+        if let Some(steps) = request.steps {
+            row.steps = steps;
+        }
+        if let Some(script) = request.script {
+            row.script = script;
+        }
+        if let Some(fields) = request.fields {
+            row.fields = fields;
+        }
+        if let Some(on_change_command) = request.on_change_command {
+            row.on_change_command = on_change_command;
+        }
+        if let Some(group) = request.group {
+            row.group = group;
+        }
+        if let Some(tags) = request.tags {
+            row.tags = tags;
+        }
+        if let Some(enabled) = request.enabled {
+            row.enabled = enabled;
+        }
+        if let Some(paused_until) = request.paused_until {
+            row.paused_until = paused_until;
+        }
+        (200, row_json(row))
+    })
+}
+
+fn delete_row(id: &str, table_data: &SharedTableData) -> (u16, Value) {
+    let mut table_data = table_data.lock().unwrap();
+    match table_data.iter().position(|row| row.id == id) {
+        Some(index) => {
+            let row = table_data.remove(index);
+            crate::mqtt::remove_row(&row.id);
+            crate::screenshot::remove_row(&row.id);
+            (200, row_json(&row))
+        }
+        None => (404, error_json("row not found")),
+    }
+}
+
+fn check_row(id: &str, table_data: &SharedTableData) -> (u16, Value) {
+    let row = table_data
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|row| row.id == id)
+        .cloned();
+    let Some(row) = row else {
+        return (404, error_json("row not found"));
+    };
+
+    // computed rows are only brought up to date with the rows they refer to
+    let result = (!row.is_computed()).then(|| fetch_value(&row));
+    {
+        let mut table_data = table_data.lock().unwrap();
+        if let Some(row) = table_data.iter_mut().find(|row| row.id == id) {
+            if let Some(result) = result {
+                crate::apply_new_value(row, result);
+            }
+        }
+        crate::update_computed_rows(&mut table_data);
+    }
+    with_row(table_data, id, |row| (200, row_json(row)))
+}
+
+fn check_all_rows(table_data: &SharedTableData, groups: &SharedGroups) -> (u16, Value) {
+    let rows = table_data.lock().unwrap().clone();
+    let now = chrono::Utc::now();
+    let new_values: Vec<(String, crate::FetchResult)> = rows
+        .iter()
+        // only paused groups have no interval, whatever the default
+        .filter(|row| !row.is_paused(now) && group::row_interval(groups, row, 0).is_some())
+        .filter(|row| !row.is_computed())
+        .map(|row| (row.id.clone(), fetch_value(row)))
+        .collect();
+
+    let mut table_data = table_data.lock().unwrap();
+    crate::update_backend_table_values(&mut table_data, new_values.into());
+    (200, rows_json(&table_data))
+}
+
+fn fetch_value(row: &ValueData) -> crate::FetchResult {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    runtime.block_on(crate::get_current_value(row))
+}
+
+/// The error response for a request the settings don't allow or that can't
+/// work, if it is one.
+fn check_request(request: &RowRequest, settings: &ApiSettings) -> Option<(u16, Value)> {
+    if !settings.allow_commands {
+        let browser = request.browser.as_ref();
+        let refused: Vec<&str> = [
+            ("on_change_command", request.on_change_command.is_some()),
+            (
+                "browser.extra_args",
+                browser.is_some_and(|browser| !browser.extra_args.trim().is_empty()),
+            ),
+            (
+                "browser.profile_dir",
+                browser.is_some_and(|browser| !browser.profile_dir.trim().is_empty()),
+            ),
+            (
+                "session.login_steps",
+                request
+                    .session
+                    .as_ref()
+                    .is_some_and(|session| !session.login_steps.is_empty()),
+            ),
+            (
+                "steps",
+                request
+                    .steps
+                    .as_ref()
+                    .is_some_and(|steps| !steps.is_empty()),
+            ),
+            (
+                "script",
+                request
+                    .script
+                    .as_ref()
+                    .is_some_and(|script| !script.trim().is_empty()),
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(field, set)| set.then_some(field))
+        .collect();
+        if !refused.is_empty() {
+            return Some((
+                403,
+                error_json(&format!(
+                    "{} only accepted when commands are allowed in the API settings",
+                    refused.join(", ")
+                )),
+            ));
+        }
+    }
+    if let Some(condition) = request
+        .browser
+        .as_ref()
+        .and_then(|browser| browser.wait_for_text.as_ref())
+    {
+        if let Err(e) = condition.compile() {
+            return Some((400, error_json(&e)));
+        }
+    }
+    let login_steps = request
+        .session
+        .as_ref()
+        .map(|session| &session.login_steps[..]);
+    for steps in [request.steps.as_deref(), login_steps]
+        .into_iter()
+        .flatten()
+    {
+        if let Err(e) = crate::steps::validate_steps(steps) {
+            return Some((400, error_json(&e)));
+        }
+    }
+    if let Some(fields) = &request.fields {
+        if let Err(e) = crate::fields::validate_fields(fields) {
+            return Some((400, error_json(&e)));
+        }
+    }
+    if let Some(crate::app::RowMode::Availability(rules)) = &request.mode {
+        if let Err(e) = crate::availability::validate_rules(rules) {
+            return Some((400, error_json(&e)));
+        }
+    }
+    None
+}
+
+/// A row as sent to clients, without its cookies and the text typed by its
+/// login steps, which may be passwords.
+fn row_json(row: &ValueData) -> Value {
+    let mut row = row.clone();
+    row.session.cookies.clear();
+    for step in &mut row.session.login_steps {
+        if step.action == crate::steps::StepAction::Type {
+            step.text.clear();
+        }
+    }
+    json!(row)
+}
+
+fn rows_json(rows: &[ValueData]) -> Value {
+    Value::Array(rows.iter().map(row_json).collect())
+}
+
+fn error_json(message: &str) -> Value {
+    json!({ "error": message })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    fn paused_row() -> (SharedTableData, String) {
+        let mut row = ValueData::new(
+            "Book".to_owned(),
+            "https://example.com".to_owned(),
+            ".price".to_owned(),
+            "$10".to_owned(),
+        );
+        row.enabled = false;
+        row.paused_until = Some(chrono::Utc::now() + chrono::Duration::days(1));
+        let id = row.id.clone();
+        (Arc::new(Mutex::new(vec![row])), id)
+    }
+
+    fn patch(body: &str, id: &str, table_data: &SharedTableData) -> u16 {
+        let url = format!("/rows/{}", id);
+        route(
+            &Method::Patch,
+            &url,
+            body,
+            &ApiSettings::default(),
+            table_data,
+            &SharedGroups::default(),
+        )
+        .0
+    }
+
+    #[test]
+    fn put_is_not_routed() {
+        let (table_data, id) = paused_row();
+        let url = format!("/rows/{}", id);
+        let (status, _) = route(
+            &Method::Put,
+            &url,
+            "{}",
+            &ApiSettings::default(),
+            &table_data,
+            &SharedGroups::default(),
+        );
+        assert_eq!(status, 404);
+    }
+
+    #[test]
+    fn paused_until_kept_unless_sent() {
+        let (table_data, id) = paused_row();
+        assert_eq!(patch(r#"{"enabled": false}"#, &id, &table_data), 200);
+        assert!(table_data.lock().unwrap()[0].paused_until.is_some());
+
+        assert_eq!(patch(r#"{"paused_until": null}"#, &id, &table_data), 200);
+        assert!(table_data.lock().unwrap()[0].paused_until.is_none());
+    }
+
+    #[test]
+    fn commands_need_opt_in() {
+        let (table_data, id) = paused_row();
+        let body = r#"{"on_change_command": "echo hi"}"#;
+        assert_eq!(patch(body, &id, &table_data), 403);
+        assert!(table_data.lock().unwrap()[0].on_change_command.is_empty());
+
+        let settings = ApiSettings {
+            allow_commands: true,
+            ..Default::default()
+        };
+        let url = format!("/rows/{}", id);
+        let (status, _) = route(
+            &Method::Patch,
+            &url,
+            body,
+            &settings,
+            &table_data,
+            &SharedGroups::default(),
+        );
+        assert_eq!(status, 200);
+        assert_eq!(table_data.lock().unwrap()[0].on_change_command, "echo hi");
+    }
+
+    #[test]
+    fn code_running_fields_need_opt_in() {
+        let bodies = [
+            r#"{"browser": {"extra_args": "--renderer-cmd-prefix=touch /tmp/x"}}"#,
+            r#"{"browser": {"profile_dir": "/home/me/.config/google-chrome"}}"#,
+            r#"{"script": "document.title"}"#,
+            r#"{"steps": [{"action": "Click", "selector": "button"}]}"#,
+            r#"{"session": {"login_steps": [{"action": "Click", "selector": "button"}]}}"#,
+        ];
+        let allowed = ApiSettings {
+            allow_commands: true,
+            ..Default::default()
+        };
+        for body in bodies {
+            let (table_data, id) = paused_row();
+            assert_eq!(patch(body, &id, &table_data), 403, "{}", body);
+            let url = format!("/rows/{}", id);
+            let (status, _) = route(
+                &Method::Patch,
+                &url,
+                body,
+                &allowed,
+                &table_data,
+                &SharedGroups::default(),
+            );
+            assert_eq!(status, 200, "{}", body);
+        }
+
+        let (table_data, id) = paused_row();
+        assert_eq!(
+            patch(r#"{"browser": {"locale": "de-DE"}}"#, &id, &table_data),
+            200
+        );
+    }
+
+    #[test]
+    fn long_waits_are_refused() {
+        let allowed = ApiSettings {
+            allow_commands: true,
+            ..Default::default()
+        };
+        let (table_data, id) = paused_row();
+        let url = format!("/rows/{}", id);
+        for body in [
+            r#"{"steps": [{"action": "WaitMs", "selector": "", "text": "86400000"}]}"#,
+            r#"{"session": {"login_steps": [{"action": "WaitMs", "selector": "", "text": "soon"}]}}"#,
+        ] {
+            let (status, _) = route(
+                &Method::Patch,
+                &url,
+                body,
+                &allowed,
+                &table_data,
+                &SharedGroups::default(),
+            );
+            assert_eq!(status, 400, "{}", body);
+        }
+        assert!(table_data.lock().unwrap()[0].steps.is_empty());
+    }
+
+    #[test]
+    fn invalid_wait_patterns_are_refused() {
+        let (table_data, id) = paused_row();
+        let body = r#"{"browser": {"wait_for_text": {"Matches": "(\\d"}}}"#;
+        assert_eq!(patch(body, &id, &table_data), 400);
+        let body = r#"{"browser": {"wait_for_text": {"Matches": "\\d"}}}"#;
+        assert_eq!(patch(body, &id, &table_data), 200);
+    }
+
+    #[test]
+    fn fields_without_a_source_are_refused() {
+        let (table_data, id) = paused_row();
+        let body = r#"{"fields": [{"name": "shipping", "selector": ""}]}"#;
+        assert_eq!(patch(body, &id, &table_data), 400);
+        let body = r#"{"fields": [{"name": "shipping", "selector": ".shipping"}]}"#;
+        assert_eq!(patch(body, &id, &table_data), 200);
+    }
+
+    #[test]
+    fn unknown_fields_are_refused() {
+        let (table_data, id) = paused_row();
+        assert_eq!(patch(r#"{"nmae": "Book"}"#, &id, &table_data), 400);
+        assert_eq!(
+            patch(r#"{"browser": {"extra_arg": "--x"}}"#, &id, &table_data),
+            400
+        );
+    }
+
+    #[test]
+    fn rows_are_sent_without_secrets() {
+        let (table_data, id) = paused_row();
+        {
+            let mut rows = table_data.lock().unwrap();
+            let session = &mut rows[0].session;
+            session.cookies.push(crate::session::StoredCookie {
+                name: "sid".to_owned(),
+                value: "secret-cookie".to_owned(),
+                domain: "example.com".to_owned(),
+                path: "/".to_owned(),
+                secure: true,
+                http_only: true,
+                expires: None,
+            });
+            session.login_steps.push(crate::steps::Step {
+                action: crate::steps::StepAction::Type,
+                selector: "#password".to_owned(),
+                text: "secret-password".to_owned(),
+            });
+        }
+        let url = format!("/rows/{}", id);
+        for url in ["/rows", url.as_str()] {
+            let (status, body) = route(
+                &Method::Get,
+                url,
+                "",
+                &ApiSettings::default(),
+                &table_data,
+                &SharedGroups::default(),
+            );
+            assert_eq!(status, 200);
+            let body = body.to_string();
+            assert!(!body.contains("secret-cookie"), "{}", url);
+            assert!(!body.contains("secret-password"), "{}", url);
+            assert!(body.contains("#password"), "{}", url);
+        }
+        assert_eq!(table_data.lock().unwrap()[0].session.cookies.len(), 1);
+    }
+
+    #[test]
+    fn schema_describes_rows() {
+        let (table_data, _) = paused_row();
+        let (status, schema) = route(
+            &Method::Get,
+            "/schema",
+            "",
+            &ApiSettings::default(),
+            &table_data,
+            &SharedGroups::default(),
+        );
+        assert_eq!(status, 200);
+        assert!(schema["row"]["properties"]["latest_value"].is_object());
+        assert!(schema["row_request"]["properties"]["paused_until"].is_object());
+    }
+
+    #[test]
+    fn element_rules_need_a_selector() {
+        let (table_data, id) = paused_row();
+        let rules = |selector: &str| {
+            json!({ "mode": { "Availability": [{ "check": "Exists", "selector": selector }] } })
+                .to_string()
+        };
+        assert_eq!(patch(&rules(" "), &id, &table_data), 400);
+        assert_eq!(patch(&rules("#add-to-cart"), &id, &table_data), 200);
+
+        let (status, _) = route(
+            &Method::Post,
+            "/rows",
+            &json!({
+                "name": "Console",
+                "link": "https://example.com",
+                "css_selector": "",
+                "mode": { "Availability": [{ "check": "Missing", "selector": "" }] },
+            })
+            .to_string(),
+            &ApiSettings::default(),
+            &table_data,
+            &SharedGroups::default(),
+        );
+        assert_eq!(status, 400);
+    }
+
+    #[test]
+    fn check_all_skips_paused_groups() {
+        let (table_data, _) = paused_row();
+        {
+            let mut rows = table_data.lock().unwrap();
+            rows[0].enabled = true;
+            rows[0].paused_until = None;
+            rows[0].group = "Holiday".to_owned();
+        }
+        let groups = SharedGroups::default();
+        group::get_or_insert(&mut groups.lock().unwrap(), "Holiday").paused = true;
+        let (status, _) = route(
+            &Method::Post,
+            "/check",
+            "",
+            &ApiSettings::default(),
+            &table_data,
+            &groups,
+        );
+        assert_eq!(status, 200);
+        let rows = table_data.lock().unwrap();
+        assert_eq!(rows[0].latest_value, "$10");
+        assert!(rows[0].last_error.is_none());
+    }
+}
@@ -1,133 +1,421 @@
 use std::{
-    collections::VecDeque,
+    collections::{HashSet, VecDeque},
+    sync::{Arc, Mutex},
     thread::{self},
 };
 
-use egui::{Button, Color32, ScrollArea, TextEdit, Ui, Window};
-use egui_extras::{Column, TableBuilder};
+use chrono::{DateTime, Local, NaiveDateTime, NaiveTime, TimeZone, Timelike, Utc};
+use egui::{Button, Color32, TextEdit, Ui, Window};
 use poll_promise::Promise;
 use std::time::Duration;
 use tokio_cron_scheduler::{Job, JobScheduler};
 use ulid::Ulid;
 
+pub mod group;
+mod migration;
+mod table;
+
+use crate::api::ApiSettings;
+use crate::availability::AvailabilityRule;
+use crate::browser::{BrowserOverrides, BrowserSettings, TextCondition};
+use crate::diff::DiffLine;
+use crate::digest::{Digest, DigestFormat, DigestSettings, SharedDigestState};
+use crate::fields::Field;
+use crate::heal::{Fingerprint, SelectorSuggestion};
+use crate::metrics::MetricsSettings;
+use crate::mqtt::MqttSettings;
+use crate::notification::{
+    Alert, Channel, NotificationSettings, Notifier, QuietHours, SharedNotifierState,
+};
+use crate::preset::{Preset, Transform};
+use crate::screenshot::ScreenshotSettings;
+use crate::session::SessionSettings;
+use crate::steps::Step;
+use crate::timestamp::{DisplayTimezone, TimeDisplaySettings};
+use group::SharedGroups;
+use table::TableView;
+
 const DEFAULT_CUSTOM_TIME_INTERVAL: u64 = 40;
+const DEFERRED_NOTIFICATIONS_CHECK_INTERVAL: u64 = 60;
+
+/// Rows shared between the UI, the scheduled job and the API server.
+pub type SharedTableData = Arc<Mutex<Vec<ValueData>>>;
 
 /// We derive Deserialize/Serialize so we can persist app state on shutdown.
 #[derive(serde::Deserialize, serde::Serialize)]
 #[serde(default)]
 pub struct ThisApp {
-    table_data: Vec<ValueData>,
-    column_names: Vec<String>,
-    selected_rows: Vec<bool>,
+    table_data: SharedTableData,
+    groups: SharedGroups,
     custom_time_interval: u64,
+    notification_settings: NotificationSettings,
+    notifier_state: SharedNotifierState,
+    digest_settings: DigestSettings,
+    digest_state: SharedDigestState,
+    api_settings: ApiSettings,
+    metrics_settings: MetricsSettings,
+    mqtt_settings: MqttSettings,
+    time_display_settings: TimeDisplaySettings,
+    browser_settings: BrowserSettings,
+    screenshot_settings: ScreenshotSettings,
+    table_view: TableView,
     #[serde(skip)]
     runtime_state: RuntimeState,
 }
 
 struct RuntimeState {
+    /// IDs of the selected rows, always a subset of the rows currently shown.
+    selected_rows: HashSet<String>,
     show_delete_confirmation_dialog: bool,
     show_add_row_dialog: bool,
     new_row_name: String,
     new_row_link: String,
     new_row_css_selector: String,
+    new_row_mode: RowMode,
+    /// One selector per line.
+    new_row_fallback_selectors: String,
+    new_row_on_change_command: String,
+    new_row_group: String,
+    new_row_tags: String,
     show_spinner: bool,
     new_row_value: String,
-    fetch_value_promise: Option<Promise<(String, String)>>,
+    fetch_value_promise: Option<Promise<(String, crate::FetchResult)>>,
+    new_row_fingerprint: Option<Fingerprint>,
+    new_row_matched_selector: Option<usize>,
+    /// Index into `presets` of the preset the selector came from, `None` for a custom one.
+    new_row_preset: Option<usize>,
+    new_row_transforms: Vec<Transform>,
+    new_row_browser: BrowserOverrides,
+    new_row_session: SessionSettings,
+    new_row_steps: Vec<Step>,
+    new_row_script: String,
+    new_row_fields: Vec<Field>,
+    /// Path of a cookie export to import into `new_row_session`.
+    new_row_cookies_file: String,
+    session_error: Option<String>,
+    record_steps_promise: Option<Promise<Result<Vec<Step>, String>>>,
+    presets: Vec<Preset>,
+    preset_error: Option<String>,
+    pick_selector_promise: Option<Promise<Result<String, String>>>,
+    picker_error: Option<String>,
     show_error_message: bool,
+    /// Why fetching the new row's value failed, when known.
+    fetch_error: Option<String>,
+    fetch_error_screenshot: Option<String>,
     fetching_latest_values: bool,
-    fetch_latest_values_promises: VecDeque<Promise<(String, String)>>,
-    resolved_promises_count: usize,
+    fetch_latest_values_promises: VecDeque<Promise<(String, crate::FetchResult)>>,
     scheduled_job_setup: bool,
-    mpsc_sender: std::sync::mpsc::Sender<VecDeque<(String, String)>>,
-    mpsc_receiver: std::sync::mpsc::Receiver<VecDeque<(String, String)>>,
+    servers_setup: bool,
     show_custom_interval_dialog: bool,
+    show_notification_settings_dialog: bool,
+    show_digest_settings_dialog: bool,
+    show_api_settings_dialog: bool,
+    show_mqtt_settings_dialog: bool,
+    show_time_display_dialog: bool,
+    show_browser_settings_dialog: bool,
+    show_screenshot_settings_dialog: bool,
+    show_inbox_dialog: bool,
+    /// Path of the screenshot being shown.
+    viewed_screenshot: Option<String>,
+    /// ID of the row whose text changes are shown.
+    viewed_diff: Option<String>,
+    /// The viewed diff, with the row ID and update time it was computed for.
+    viewed_diff_lines: Option<(String, DateTime<Utc>, Vec<DiffLine>)>,
+    show_group_tags_dialog: bool,
+    bulk_group: String,
+    bulk_tags: String,
+    show_pause_dialog: bool,
+    /// Local time as "%Y-%m-%d %H:%M", empty to pause until resumed.
+    pause_until: String,
+    load_error: Option<String>,
+    /// Set when saved data couldn't be loaded or backed up, so it must not be overwritten.
+    skip_save: bool,
 }
 
-#[derive(serde::Deserialize, serde::Serialize, Clone)]
+#[derive(serde::Deserialize, serde::Serialize, schemars::JsonSchema, Clone)]
 pub struct ValueData {
     pub id: String,
     pub name: String,
     pub link: String,
+    #[serde(default)]
+    pub mode: RowMode,
     pub css_selector: String,
+    /// Tried in order when `css_selector` finds nothing.
+    #[serde(default)]
+    pub fallback_selectors: Vec<String>,
+    /// Index into `selectors()` of the selector the latest value was read with.
+    #[serde(default)]
+    pub matched_selector: Option<usize>,
     pub previous_value: String,
     pub latest_value: String,
-    pub last_updated: String,
+    pub last_updated: DateTime<Utc>,
+    #[serde(default)]
+    pub lowest_value: Option<f64>,
+    #[serde(default)]
+    pub highest_value: Option<f64>,
+    /// Shell command run when the value changes, empty for none.
+    #[serde(default)]
+    pub on_change_command: String,
+    /// Name of the group the row is shown in, empty for ungrouped.
+    #[serde(default)]
+    pub group: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Disabled rows aren't checked, until `paused_until` if set.
+    #[serde(default = "enabled_by_default")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub paused_until: Option<DateTime<Utc>>,
+    /// Last seen shape of the matched element, used to find it again if the selector breaks.
+    #[serde(default)]
+    pub fingerprint: Option<Fingerprint>,
+    #[serde(default)]
+    pub selector_suggestion: Option<SelectorSuggestion>,
+    /// Clean-up steps applied to the text read from the page.
+    #[serde(default)]
+    pub transforms: Vec<Transform>,
+    #[serde(default)]
+    pub browser: BrowserOverrides,
+    /// Cookies and login steps for pages behind a sign-in.
+    #[serde(default)]
+    pub session: SessionSettings,
+    /// Run on the page before the value is read, e.g. to dismiss a cookie banner.
+    #[serde(default)]
+    pub steps: Vec<Step>,
+    /// JavaScript reading the value instead of the selectors, empty to use the selectors.
+    #[serde(default)]
+    pub script: String,
+    /// More values read from the same page load.
+    #[serde(default)]
+    pub fields: Vec<Field>,
+    /// Why the latest fetch failed.
+    #[serde(default)]
+    pub last_error: Option<String>,
+    /// Picture of the page from the latest failed fetch.
+    #[serde(default)]
+    pub failure_screenshot: Option<String>,
+    #[serde(default)]
+    pub history: Vec<HistoryEntry>,
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+/// What a row tracks.
+#[derive(serde::Deserialize, serde::Serialize, schemars::JsonSchema, Clone, Default, PartialEq)]
+pub enum RowMode {
+    /// A single value, such as a price.
+    #[default]
+    Value,
+    /// All text of a region, compared line by line. `html` keeps the markup.
+    Text { html: bool },
+    /// Whether an item can be bought, "In stock" when all rules are met.
+    Availability(Vec<AvailabilityRule>),
+    /// Not read from a page but computed from other rows by name, e.g.
+    /// `min("Shop A", "Shop B")`, whenever they update.
+    Computed { formula: String },
+}
+
+impl RowMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RowMode::Value => "Value",
+            RowMode::Text { .. } => "Text (diff)",
+            RowMode::Availability(_) => "Availability",
+            RowMode::Computed { .. } => "Computed",
+        }
+    }
+
+    pub fn get_modes() -> [RowMode; 4] {
+        [
+            RowMode::Value,
+            RowMode::Text { html: false },
+            RowMode::Availability(Vec::new()),
+            RowMode::Computed {
+                formula: String::new(),
+            },
+        ]
+    }
+
+    /// Whether values are numbers with a lowest, highest and change.
+    pub fn is_numeric(&self) -> bool {
+        matches!(self, RowMode::Value | RowMode::Computed { .. })
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize, schemars::JsonSchema, Clone)]
+pub struct HistoryEntry {
+    pub value: String,
+    pub timestamp: DateTime<Utc>,
+    /// Path of a picture of the page taken when the value changed.
+    #[serde(default)]
+    pub screenshot: Option<String>,
+    /// Name and value of each of the row's fields at the time.
+    #[serde(default)]
+    pub fields: Vec<(String, String)>,
+}
+
+impl ValueData {
+    pub fn new(name: String, link: String, css_selector: String, value: String) -> Self {
+        let cur_date_time = Utc::now();
+        Self {
+            id: Ulid::new().to_string(),
+            name,
+            link,
+            mode: RowMode::Value,
+            css_selector,
+            fallback_selectors: Vec::new(),
+            matched_selector: None,
+            previous_value: value.clone(),
+            latest_value: value.clone(),
+            last_updated: cur_date_time,
+            lowest_value: crate::parse_number(&value),
+            highest_value: crate::parse_number(&value),
+            on_change_command: String::new(),
+            group: String::new(),
+            tags: Vec::new(),
+            enabled: true,
+            paused_until: None,
+            fingerprint: None,
+            selector_suggestion: None,
+            transforms: Vec::new(),
+            browser: BrowserOverrides::default(),
+            session: SessionSettings::default(),
+            steps: Vec::new(),
+            script: String::new(),
+            fields: Vec::new(),
+            last_error: None,
+            failure_screenshot: None,
+            history: vec![HistoryEntry {
+                value,
+                timestamp: cur_date_time,
+                screenshot: None,
+                fields: Vec::new(),
+            }],
+        }
+    }
+
+    /// The primary selector followed by the fallbacks.
+    pub fn selectors(&self) -> Vec<&str> {
+        std::iter::once(self.css_selector.as_str())
+            .chain(self.fallback_selectors.iter().map(String::as_str))
+            .collect()
+    }
+
+    /// `value` shortened to one line for rows tracking whole texts.
+    pub fn display_value(&self, value: &str) -> String {
+        const MAX_CHARS: usize = 60;
+        match self.mode {
+            RowMode::Value | RowMode::Availability(_) | RowMode::Computed { .. } => {
+                value.to_owned()
+            }
+            RowMode::Text { .. } if value.is_empty() => String::new(),
+            RowMode::Text { .. } => {
+                let first_line = value.lines().find(|line| !line.trim().is_empty());
+                let mut shown: String = first_line
+                    .unwrap_or_default()
+                    .trim()
+                    .chars()
+                    .take(MAX_CHARS)
+                    .collect();
+                if first_line.is_some_and(|line| line.trim().chars().count() > MAX_CHARS) {
+                    shown.push('…');
+                }
+                format!("{} ({} lines)", shown, value.lines().count())
+            }
+        }
+    }
+
+    /// Computed rows have no page to load.
+    pub fn is_computed(&self) -> bool {
+        matches!(self.mode, RowMode::Computed { .. })
+    }
+
+    pub fn is_paused(&self, now: DateTime<Utc>) -> bool {
+        !self.enabled && self.paused_until.map_or(true, |until| now < until)
+    }
 }
 
 impl Default for ThisApp {
     fn default() -> Self {
-        let (mpsc_sender, mpsc_receiver) = std::sync::mpsc::channel();
         Self {
-            table_data: Vec::new(),
-            column_names: vec![
-                "Name".to_owned(),
-                "Link".to_owned(),
-                "CSS Selector".to_owned(),
-                "Previous Value".to_owned(),
-                "Latest Value".to_owned(),
-                "Last Updated".to_owned(),
-            ],
-            selected_rows: vec![false; 0],
+            table_data: Arc::new(Mutex::new(Vec::new())),
+            groups: Arc::new(Mutex::new(Vec::new())),
             custom_time_interval: DEFAULT_CUSTOM_TIME_INTERVAL,
+            notification_settings: NotificationSettings::default(),
+            notifier_state: SharedNotifierState::default(),
+            digest_settings: DigestSettings::default(),
+            digest_state: SharedDigestState::default(),
+            api_settings: ApiSettings::default(),
+            metrics_settings: MetricsSettings::default(),
+            mqtt_settings: MqttSettings::default(),
+            time_display_settings: TimeDisplaySettings::default(),
+            browser_settings: BrowserSettings::default(),
+            screenshot_settings: ScreenshotSettings::default(),
+            table_view: TableView::default(),
             runtime_state: RuntimeState {
+                selected_rows: HashSet::new(),
                 show_delete_confirmation_dialog: false,
                 show_add_row_dialog: false,
                 new_row_name: String::new(),
                 new_row_link: String::new(),
                 new_row_css_selector: String::new(),
+                new_row_mode: RowMode::Value,
+                new_row_fallback_selectors: String::new(),
+                new_row_on_change_command: String::new(),
+                new_row_group: String::new(),
+                new_row_tags: String::new(),
                 show_spinner: false,
                 new_row_value: String::new(),
                 fetch_value_promise: None,
+                new_row_fingerprint: None,
+                new_row_matched_selector: None,
+                new_row_preset: None,
+                new_row_transforms: Vec::new(),
+                new_row_browser: BrowserOverrides::default(),
+                new_row_session: SessionSettings::default(),
+                new_row_steps: Vec::new(),
+                new_row_script: String::new(),
+                new_row_fields: Vec::new(),
+                new_row_cookies_file: String::new(),
+                session_error: None,
+                record_steps_promise: None,
+                presets: Vec::new(),
+                preset_error: None,
+                pick_selector_promise: None,
+                picker_error: None,
                 show_error_message: false,
+                fetch_error: None,
+                fetch_error_screenshot: None,
                 fetching_latest_values: false,
                 fetch_latest_values_promises: VecDeque::new(),
-                resolved_promises_count: 0,
                 scheduled_job_setup: false,
-                mpsc_sender,
-                mpsc_receiver,
+                servers_setup: false,
                 show_custom_interval_dialog: false,
+                show_notification_settings_dialog: false,
+                show_digest_settings_dialog: false,
+                show_api_settings_dialog: false,
+                show_mqtt_settings_dialog: false,
+                show_time_display_dialog: false,
+                show_browser_settings_dialog: false,
+                show_screenshot_settings_dialog: false,
+                show_inbox_dialog: false,
+                viewed_screenshot: None,
+                viewed_diff: None,
+                viewed_diff_lines: None,
+                show_group_tags_dialog: false,
+                bulk_group: String::new(),
+                bulk_tags: String::new(),
+                show_pause_dialog: false,
+                pause_until: String::new(),
+                load_error: None,
+                skip_save: false,
             },
         }
     }
 }
 
-#[derive(PartialEq, Clone, Copy)]
-enum SelectorOption {
-    Custom,
-    AmazonItemPrice,
-    AmazonBookPrice,
-    EbayPrice,
-}
-
-impl SelectorOption {
-    fn as_str(&self) -> &'static str {
-        match self {
-            SelectorOption::Custom => "Custom",
-            SelectorOption::AmazonItemPrice => "Amazon Item Price",
-            SelectorOption::AmazonBookPrice => "Amazon Book Price",
-            SelectorOption::EbayPrice => "Ebay Price",
-        }
-    }
-
-    fn get_options() -> [SelectorOption; 4] {
-        [
-            SelectorOption::Custom,
-            SelectorOption::AmazonItemPrice,
-            SelectorOption::AmazonBookPrice,
-            SelectorOption::EbayPrice,
-        ]
-    }
-
-    fn get_selector(&self) -> &'static str {
-        match self {
-            SelectorOption::Custom => "",
-            SelectorOption::AmazonItemPrice => ".a-offscreen",
-            SelectorOption::AmazonBookPrice => ".aok-offscreen",
-            SelectorOption::EbayPrice => "div.x-price-primary span.ux-textspans",
-        }
-    }
-}
-
 impl ThisApp {
     /// Called once before the first frame.
     pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
@@ -136,18 +424,42 @@ impl ThisApp {
 
         // Load previous app state (if any).
         // Note that you must enable the `persistence` feature for this to work.
-        if let Some(storage) = cc.storage {
-            return eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default();
-        }
+        let mut app = match cc.storage.map(migration::load) {
+            Some(Ok(Some(app))) => app,
+            Some(Ok(None)) | None => Self::default(),
+            Some(Err(error)) => {
+                eprintln!("Failed to load saved data: {}", error.message);
+                let mut app = Self::default();
+                app.runtime_state.skip_save = !error.backed_up;
+                app.runtime_state.load_error = Some(error.message);
+                app
+            }
+        };
+        app.reload_presets();
+        crate::browser::set_global_settings(&app.browser_settings);
+        crate::screenshot::set_global_settings(&app.screenshot_settings);
+        egui_extras::install_image_loaders(&cc.egui_ctx);
+        app
+    }
 
-        Default::default()
+    fn reload_presets(&mut self) {
+        let (presets, error) = crate::preset::load_presets();
+        self.runtime_state.presets = presets;
+        self.runtime_state.preset_error = error;
     }
 }
 
 impl eframe::App for ThisApp {
     /// Called by the frame work to save state before shutdown.
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        if self.runtime_state.skip_save {
+            return;
+        }
         eframe::set_value(storage, eframe::APP_KEY, self);
+        storage.set_string(
+            migration::SCHEMA_VERSION_KEY,
+            migration::CURRENT_SCHEMA_VERSION.to_string(),
+        );
     }
 
     /// Called each time the UI needs repainting, which may be many times per second.
@@ -158,12 +470,18 @@ impl eframe::App for ThisApp {
         // Request a auto-repaint after 1 second
         // ctx.request_repaint_after(Duration::from_secs(1));
 
+        // keep relative times ("5 min ago") current
+        if self.time_display_settings.relative {
+            ctx.request_repaint_after(Duration::from_secs(30));
+        }
+
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
             // The top panel is often a good place for a menu bar:
             Self::menu_bar(self, ui);
         });
 
         egui::CentralPanel::default().show(ctx, |ui| {
+            Self::table_filter_ui(self, ui);
             Self::table_ui(self, ui);
             Self::code_link(ui);
             Self::condional_components(self, ctx);
@@ -174,7 +492,26 @@ impl eframe::App for ThisApp {
             if let Some(promise) = self.runtime_state.fetch_value_promise.as_mut() {
                 if let Some(value) = promise.ready() {
                     self.runtime_state.show_spinner = false;
-                    self.runtime_state.new_row_value = value.1.clone();
+                    self.runtime_state.new_row_value = value.1.value.clone();
+                    self.runtime_state.new_row_fingerprint = value.1.fingerprint.clone();
+                    self.runtime_state.new_row_matched_selector = value.1.matched_selector;
+                    self.runtime_state.show_error_message = value.1.value.is_empty();
+                    self.runtime_state.fetch_error = value.1.error.clone();
+                    if let Some(path) = std::mem::replace(
+                        &mut self.runtime_state.fetch_error_screenshot,
+                        value.1.screenshot.clone(),
+                    ) {
+                        crate::screenshot::remove_draft(&path);
+                    }
+                    for (index, field) in self.runtime_state.new_row_fields.iter_mut().enumerate() {
+                        field.value = value.1.fields.get(index).cloned().unwrap_or_default();
+                    }
+                    if let Some(cookies) = value.1.cookies.clone() {
+                        crate::session::merge_cookies(
+                            &mut self.runtime_state.new_row_session.cookies,
+                            cookies,
+                        );
+                    }
                     self.runtime_state.fetch_value_promise = None; // Clear the promise after completion
                 } else {
                     self.runtime_state.show_spinner = true;
@@ -182,19 +519,48 @@ impl eframe::App for ThisApp {
             }
         }
 
+        if let Some(promise) = &self.runtime_state.pick_selector_promise {
+            if let Some(result) = promise.ready() {
+                match result {
+                    Ok(selector) => {
+                        self.runtime_state.new_row_css_selector = selector.clone();
+                        self.runtime_state.new_row_preset = None;
+                        self.runtime_state.new_row_transforms.clear();
+                    }
+                    Err(error) => self.runtime_state.picker_error = Some(error.clone()),
+                }
+                self.runtime_state.pick_selector_promise = None;
+            } else {
+                ctx.request_repaint_after(Duration::from_millis(250));
+            }
+        }
+
+        if let Some(promise) = &self.runtime_state.record_steps_promise {
+            if let Some(result) = promise.ready() {
+                match result {
+                    Ok(steps) => self.runtime_state.new_row_session.login_steps = steps.clone(),
+                    Err(error) => {
+                        self.runtime_state.session_error =
+                            Some(format!("Couldn't record login: {}", error))
+                    }
+                }
+                self.runtime_state.record_steps_promise = None;
+            } else {
+                ctx.request_repaint_after(Duration::from_millis(250));
+            }
+        }
+
         if self.runtime_state.fetching_latest_values {
             if let Some(promise) = self.runtime_state.fetch_latest_values_promises.front() {
-                if let Some((id, value)) = promise.ready() {
+                if let Some((id, result)) = promise.ready() {
                     println!("promise ready");
-                    self.update_value(id.clone(), value.clone());
+                    self.update_value(id.clone(), result.clone());
                     self.runtime_state.fetch_latest_values_promises.pop_front();
-                    self.runtime_state.resolved_promises_count += 1;
-                }
-                if self.runtime_state.resolved_promises_count == self.table_data.len() {
-                    self.runtime_state.fetching_latest_values = false;
-                    self.runtime_state.resolved_promises_count = 0;
                 }
             }
+            if self.runtime_state.fetch_latest_values_promises.is_empty() {
+                self.runtime_state.fetching_latest_values = false;
+            }
         }
 
         //initialize the sheduled job
@@ -204,11 +570,29 @@ impl eframe::App for ThisApp {
             self.runtime_state.scheduled_job_setup = true;
         }
 
-        //poll the mpsc_receiver
-        if let Ok(new_values) = self.runtime_state.mpsc_receiver.try_recv() {
-            for (id, value) in new_values {
-                self.update_value(id, value);
+        //start the local API and metrics servers
+        if !self.runtime_state.servers_setup {
+            if self.api_settings.enabled {
+                crate::api::start_api_server(
+                    self.api_settings.clone(),
+                    self.table_data.clone(),
+                    self.groups.clone(),
+                    ctx.clone(),
+                );
+            }
+            if self.metrics_settings.enabled {
+                crate::metrics::start_metrics_server(
+                    self.metrics_settings.clone(),
+                    self.table_data.clone(),
+                );
+            }
+            if self.mqtt_settings.enabled {
+                crate::mqtt::start_mqtt_publisher(
+                    self.mqtt_settings.clone(),
+                    &self.table_data.lock().unwrap(),
+                );
             }
+            self.runtime_state.servers_setup = true;
         }
     }
 }
@@ -218,65 +602,36 @@ impl ThisApp {
         self.runtime_state.new_row_name.clear();
         self.runtime_state.new_row_link.clear();
         self.runtime_state.new_row_css_selector.clear();
+        self.runtime_state.new_row_mode = RowMode::Value;
+        self.runtime_state.new_row_fallback_selectors.clear();
+        self.runtime_state.new_row_on_change_command.clear();
+        self.runtime_state.new_row_group.clear();
+        self.runtime_state.new_row_tags.clear();
         self.runtime_state.new_row_value.clear();
+        self.runtime_state.new_row_fingerprint = None;
+        self.runtime_state.new_row_matched_selector = None;
+        self.runtime_state.new_row_preset = None;
+        self.runtime_state.new_row_transforms.clear();
+        self.runtime_state.new_row_browser = BrowserOverrides::default();
+        self.runtime_state.new_row_session = SessionSettings::default();
+        self.runtime_state.new_row_steps.clear();
+        self.runtime_state.new_row_script.clear();
+        self.runtime_state.new_row_fields.clear();
+        self.runtime_state.new_row_cookies_file.clear();
+        self.runtime_state.session_error = None;
         self.runtime_state.show_spinner = false;
         self.runtime_state.show_error_message = false;
+        self.runtime_state.fetch_error = None;
+        self.discard_draft_screenshot();
+        self.runtime_state.picker_error = None;
     }
 
-    fn table_ui(&mut self, ui: &mut Ui) {
-        ScrollArea::horizontal().show(ui, |ui| {
-            TableBuilder::new(ui)
-                .striped(true)
-                .resizable(true)
-                .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
-                .column(Column::auto()) // Checkbox column
-                .column(Column::auto().clip(true))
-                .column(Column::auto().clip(true))
-                .columns(Column::auto().clip(true), self.column_names.len() - 2)
-                .header(20.0, |mut header| {
-                    header.col(|ui| {
-                        ui.strong("Select");
-                    });
-                    for col_name in &self.column_names {
-                        header.col(|ui| {
-                            ui.strong(col_name);
-                        });
-                    }
-                })
-                .body(|mut body| {
-                    for (row_index, row_data) in self.table_data.iter().enumerate() {
-                        let row_is_selected = self.selected_rows[row_index];
-                        body.row(25.0, |mut row| {
-                            row.col(|ui| {
-                                if ui
-                                    .checkbox(&mut self.selected_rows[row_index], "")
-                                    .changed()
-                                {
-                                    ui.ctx().request_repaint(); // Ensure UI updates immediately
-                                }
-                            });
-                            let row_values = vec![
-                                &row_data.name,
-                                &row_data.link,
-                                &row_data.css_selector,
-                                &row_data.previous_value,
-                                &row_data.latest_value,
-                                &row_data.last_updated,
-                            ];
-                            for value in row_values {
-                                row.col(|ui| {
-                                    let text_color = if row_is_selected {
-                                        ui.ctx().style().visuals.strong_text_color()
-                                    } else {
-                                        ui.ctx().style().visuals.text_color()
-                                    };
-                                    ui.colored_label(text_color, value);
-                                });
-                            }
-                        });
-                    }
-                });
-        });
+    /// The add dialog fetches under a throwaway row ID, so nothing else
+    /// would remove its screenshot.
+    fn discard_draft_screenshot(&mut self) {
+        if let Some(path) = self.runtime_state.fetch_error_screenshot.take() {
+            crate::screenshot::remove_draft(&path);
+        }
     }
 
     fn code_link(ui: &mut Ui) {
@@ -301,6 +656,20 @@ impl ThisApp {
                 Self::delete_selected_rows(self);
             }
 
+            if ui.button("🏷 Group/Tag Selected Rows").clicked() {
+                self.runtime_state.show_group_tags_dialog = true;
+            }
+
+            if ui.button("⏸ Pause Selected Rows").clicked()
+                && !self.runtime_state.selected_rows.is_empty()
+            {
+                self.runtime_state.show_pause_dialog = true;
+            }
+
+            if ui.button("▶ Resume Selected Rows").clicked() {
+                self.set_selected_rows_paused(false, None);
+            }
+
             // Fetch latest values button
             if ui
                 .add_enabled(
@@ -312,6 +681,10 @@ impl ThisApp {
                 self.fetch_latest_values();
             }
 
+            if ui.button("📥 Inbox").clicked() {
+                self.runtime_state.show_inbox_dialog = true;
+            }
+
             ui.menu_button("📐 Settings", |ui| self.nested_menus(ui));
 
             // dark/light mode toggle button
@@ -325,7 +698,16 @@ impl ThisApp {
         ui.set_max_width(200.0); // To make sure we wrap long text
 
         if ui.button("🔔 Test notification").clicked() {
-            crate::show_notifcation(&"NAME".to_string(), &"X".to_string(), &"Y".to_string());
+            let alert = Alert {
+                id: String::new(),
+                name: "NAME".to_string(),
+                old_value: "X".to_string(),
+                new_value: "Y".to_string(),
+                changed_at: Utc::now(),
+                mode: RowMode::Value,
+                screenshot: None,
+            };
+            crate::show_notifcation(&alert.body());
             ui.close_menu();
         }
 
@@ -333,15 +715,59 @@ impl ThisApp {
             self.runtime_state.show_custom_interval_dialog = true;
             ui.close_menu();
         }
+
+        if ui.button("🔕 Notification settings").clicked() {
+            self.runtime_state.show_notification_settings_dialog = true;
+            ui.close_menu();
+        }
+
+        if ui.button("📰 Digest report").clicked() {
+            self.runtime_state.show_digest_settings_dialog = true;
+            ui.close_menu();
+        }
+
+        if ui.button("🔌 Local API").clicked() {
+            self.runtime_state.show_api_settings_dialog = true;
+            ui.close_menu();
+        }
+
+        if ui.button("📡 MQTT").clicked() {
+            self.runtime_state.show_mqtt_settings_dialog = true;
+            ui.close_menu();
+        }
+
+        if ui.button("🕒 Time display").clicked() {
+            self.runtime_state.show_time_display_dialog = true;
+            ui.close_menu();
+        }
+
+        if ui.button("🌐 Browser").clicked() {
+            self.runtime_state.show_browser_settings_dialog = true;
+            ui.close_menu();
+        }
+
+        if ui.button("📷 Screenshots").clicked() {
+            self.runtime_state.show_screenshot_settings_dialog = true;
+            ui.close_menu();
+        }
+
+        let presets_path = crate::preset::user_presets_path()
+            .map_or("not available".to_owned(), |path| {
+                path.display().to_string()
+            });
+        if ui
+            .button("🧩 Reload selector presets")
+            .on_hover_text(format!("Your presets file: {}", presets_path))
+            .clicked()
+        {
+            self.reload_presets();
+            self.runtime_state.new_row_preset = None;
+            ui.close_menu();
+        }
     }
 
     fn delete_selected_rows(&mut self) {
-        let selected_count = self
-            .selected_rows
-            .iter()
-            .filter(|&&selected| selected)
-            .count();
-        if selected_count > 0 {
+        if !self.runtime_state.selected_rows.is_empty() {
             self.runtime_state.show_delete_confirmation_dialog = true;
         }
     }
@@ -350,6 +776,19 @@ impl ThisApp {
         Self::add_row_dialog(self, ctx);
         Self::delete_confirmation_dialog(self, ctx);
         Self::custom_interval_dialog(self, ctx);
+        Self::notification_settings_dialog(self, ctx);
+        Self::digest_settings_dialog(self, ctx);
+        Self::api_settings_dialog(self, ctx);
+        Self::mqtt_settings_dialog(self, ctx);
+        Self::time_display_dialog(self, ctx);
+        Self::browser_settings_dialog(self, ctx);
+        Self::screenshot_settings_dialog(self, ctx);
+        Self::inbox_dialog(self, ctx);
+        Self::screenshot_viewer(self, ctx);
+        Self::diff_viewer(self, ctx);
+        Self::group_tags_dialog(self, ctx);
+        Self::pause_dialog(self, ctx);
+        Self::load_error_dialog(self, ctx);
     }
 
     fn add_row_dialog(&mut self, ctx: &egui::Context) {
@@ -367,34 +806,132 @@ impl ThisApp {
                     });
                     ui.horizontal(|ui| {
                         ui.label("Link:");
-                        ui.add(TextEdit::singleline(&mut this.runtime_state.new_row_link).hint_text("enter link of the webpage"));
+                        let link_edit = ui.add(TextEdit::singleline(&mut this.runtime_state.new_row_link).hint_text("enter link of the webpage"));
+                        // don't replace a selector the user typed themselves
+                        if link_edit.changed() && (this.runtime_state.new_row_preset.is_some() || this.runtime_state.new_row_css_selector.is_empty()) {
+                            let preset = this.runtime_state.presets.iter().position(|preset| preset.matches_link(&this.runtime_state.new_row_link));
+                            this.select_preset(preset);
+                        }
                     });
                     ui.horizontal(|ui| {
-                        ui.label("CSS Selector:");
-                        let options = SelectorOption::get_options();
-                        let current_option = options.iter()
-                        .find(|&&opt| opt.get_selector() == this.runtime_state.new_row_css_selector)
-                        .copied()
-                        .unwrap_or(SelectorOption::Custom);
-
-                        let mut selected_option = current_option;
-                        egui::ComboBox::from_label("")
-                            .selected_text(selected_option.as_str())
+                        ui.label("Track:");
+                        let mode = &mut this.runtime_state.new_row_mode;
+                        egui::ComboBox::from_id_salt("new_row_mode")
+                            .selected_text(mode.as_str())
                             .show_ui(ui, |ui| {
-                                for option in options {
-                                    if ui.selectable_value(&mut selected_option, option, option.as_str()).clicked() {
-                                        this.runtime_state.new_row_css_selector = selected_option.get_selector().to_string();
+                                for option in RowMode::get_modes() {
+                                    let selected = std::mem::discriminant(mode) == std::mem::discriminant(&option);
+                                    if ui.selectable_label(selected, option.as_str()).clicked() && !selected {
+                                        *mode = option;
                                     }
                                 }
                             });
-
-                        ui.add_enabled(
-                            selected_option == SelectorOption::Custom,
-                            TextEdit::singleline(&mut this.runtime_state.new_row_css_selector)
-                                .hint_text("enter css selector of the thing you want to track"),
-                        );
-                        ui.hyperlink_to("what?", "https://github.com/Ashu999/web-value-tracker?tab=readme-ov-file#css-selectors-how");
+                        if let RowMode::Text { html } = mode {
+                            ui.checkbox(html, "Keep HTML");
+                            ui.label("use the selector body for the whole page");
+                        }
                     });
+                    if let RowMode::Availability(rules) = &mut this.runtime_state.new_row_mode {
+                        ui.label("In stock when all of these hold, the selector below isn't used:");
+                        crate::availability::rules_ui(ui, rules);
+                    }
+                    if let RowMode::Computed { formula } = &mut this.runtime_state.new_row_mode {
+                        ui.horizontal(|ui| {
+                            ui.label("Formula:");
+                            ui.add(TextEdit::singleline(formula).hint_text(r#"e.g. min("Shop A", "Shop B")"#));
+                        });
+                        ui.label("Refers to other rows by name, quoted when it has spaces, and to their fields as \"name.field\". Supports + - * / and min, max, sum, avg, abs, round.");
+                    }
+                    let computed = matches!(this.runtime_state.new_row_mode, RowMode::Computed { .. });
+                    if !computed {
+                        ui.horizontal(|ui| {
+                            ui.label("CSS Selector:");
+                            let mut selected_preset = this.runtime_state.new_row_preset;
+                            let selected_text = selected_preset.map_or("Custom", |index| this.runtime_state.presets[index].name.as_str());
+                            egui::ComboBox::from_label("")
+                                .selected_text(selected_text)
+                                .show_ui(ui, |ui| {
+                                    ui.selectable_value(&mut selected_preset, None, "Custom");
+                                    for (index, preset) in this.runtime_state.presets.iter().enumerate() {
+                                        ui.selectable_value(&mut selected_preset, Some(index), &preset.name);
+                                    }
+                                });
+                            if selected_preset != this.runtime_state.new_row_preset {
+                                this.select_preset(selected_preset);
+                            }
+
+                            ui.add_enabled(
+                                selected_preset.is_none(),
+                                TextEdit::singleline(&mut this.runtime_state.new_row_css_selector)
+                                    .hint_text("enter css selector of the thing you want to track"),
+                            );
+                            let picking = this.runtime_state.pick_selector_promise.is_some();
+                            if ui
+                                .add_enabled(!picking && !this.runtime_state.new_row_link.is_empty(), Button::new("🎯 Pick"))
+                                .on_hover_text("Open the page and click the value to track")
+                                .clicked()
+                            {
+                                this.runtime_state.picker_error = None;
+                                this.runtime_state.pick_selector_promise = Some(
+                                    crate::picker::pick_selector_promise(
+                                        this.runtime_state.new_row_link.clone(),
+                                        this.runtime_state.new_row_browser.clone(),
+                                    ),
+                                );
+                            }
+                            if picking {
+                                ui.spinner();
+                            }
+                            ui.hyperlink_to("what?", "https://github.com/Ashu999/web-value-tracker?tab=readme-ov-file#css-selectors-how");
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Fallback selectors:");
+                            ui.add_enabled(
+                                this.runtime_state.new_row_preset.is_none(),
+                                TextEdit::multiline(&mut this.runtime_state.new_row_fallback_selectors)
+                                    .desired_rows(2)
+                                    .hint_text("optional, one per line, tried in order when the selector finds nothing"),
+                            );
+                        });
+                        egui::CollapsingHeader::new("Browser overrides")
+                            .default_open(!this.runtime_state.new_row_browser.is_empty())
+                            .show(ui, |ui| browser_overrides_ui(ui, &mut this.runtime_state.new_row_browser));
+                        egui::CollapsingHeader::new("Login & cookies")
+                            .default_open(!this.runtime_state.new_row_session.login_steps.is_empty())
+                            .show(ui, |ui| this.session_ui(ui));
+                        egui::CollapsingHeader::new("Steps before reading")
+                            .default_open(!this.runtime_state.new_row_steps.is_empty())
+                            .show(ui, |ui| {
+                                ui.label("Run on the page first, e.g. accept cookies or choose a size.");
+                                crate::steps::steps_ui(ui, "page_steps", &mut this.runtime_state.new_row_steps);
+                            });
+                        egui::CollapsingHeader::new("Extractor script")
+                            .default_open(!this.runtime_state.new_row_script.is_empty())
+                            .show(ui, |ui| {
+                                ui.label("Used instead of the selectors. An expression, or a function body with `return`; may be async.");
+                                ui.add(
+                                    TextEdit::multiline(&mut this.runtime_state.new_row_script)
+                                        .code_editor()
+                                        .desired_rows(3)
+                                        .hint_text("e.g. document.querySelectorAll('.item').length"),
+                                );
+                            });
+                        egui::CollapsingHeader::new("More fields")
+                            .default_open(!this.runtime_state.new_row_fields.is_empty())
+                            .show(ui, |ui| {
+                                ui.label("Read from the same page load, or computed with + - * / and min, max, sum, avg, abs, round. The tracked value is `value`.");
+                                crate::fields::fields_ui(ui, &mut this.runtime_state.new_row_fields);
+                            });
+                        if let Some(hint) = crate::heal::fragility_hint(&this.runtime_state.new_row_css_selector) {
+                            ui.colored_label(Color32::YELLOW, format!("⚠ {}", hint));
+                        }
+                    }
+                    if let Some(error) = &this.runtime_state.preset_error {
+                        ui.colored_label(Color32::RED, format!("Couldn't load your presets: {}", error));
+                    }
+                    if let Some(error) = &this.runtime_state.picker_error {
+                        ui.colored_label(Color32::RED, format!("Couldn't pick an element: {}", error));
+                    }
 
                     ui.horizontal(|ui| {
                         if ui.button("Fetch Value").clicked() {
@@ -402,19 +939,48 @@ impl ThisApp {
                             let css_selector = this.runtime_state.new_row_css_selector.clone();
                             this.runtime_state.show_spinner = true;
 
-                            this.runtime_state.fetch_value_promise = Some(
-                                crate::get_web_value_promise(String::new(), link, css_selector),
-                            );
+                            let mut draft = ValueData::new(String::new(), link, css_selector, String::new());
+                            draft.fallback_selectors = parse_lines(&this.runtime_state.new_row_fallback_selectors);
+                            draft.mode = this.runtime_state.new_row_mode.clone();
+                            draft.transforms = this.runtime_state.new_row_transforms.clone();
+                            draft.browser = this.runtime_state.new_row_browser.clone();
+                            draft.session = this.runtime_state.new_row_session.clone();
+                            draft.steps = this.runtime_state.new_row_steps.clone();
+                            draft.script = this.runtime_state.new_row_script.clone();
+                            draft.fields = this.runtime_state.new_row_fields.clone();
+                            this.runtime_state.fetch_value_promise = Some(if draft.is_computed() {
+                                draft.name = this.runtime_state.new_row_name.clone();
+                                let result = crate::compute_value(&this.table_data.lock().unwrap(), &draft);
+                                Promise::from_ready((draft.id, result))
+                            } else {
+                                crate::get_web_value_promise(draft)
+                            });
                         }
                         if this.runtime_state.show_spinner {
                             ui.spinner();
                         }
                         if this.runtime_state.show_error_message {
                             ui.horizontal(|ui| {
-                                ui.label("Error fetching value: check Link or CSS Selector.");
+                                let reason = this.runtime_state.fetch_error.as_deref().unwrap_or("check Link or CSS Selector.");
+                                ui.colored_label(Color32::RED, format!("Error fetching value: {}", reason));
+                                if let Some(path) = &this.runtime_state.fetch_error_screenshot {
+                                    if ui.small_button("📷").on_hover_text("What the page looked like").clicked() {
+                                        this.runtime_state.viewed_screenshot = Some(path.clone());
+                                    }
+                                }
                             });
                         }
                     });
+                    ui.horizontal(|ui| {
+                        ui.label("On change command:");
+                        ui.add(TextEdit::singleline(&mut this.runtime_state.new_row_on_change_command).hint_text("optional, gets WVT_NAME, WVT_OLD_VALUE, WVT_NEW_VALUE (cut to 8 KB), WVT_URL and JSON on stdin"));
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Group:");
+                        ui.add(TextEdit::singleline(&mut this.runtime_state.new_row_group).hint_text("optional"));
+                        ui.label("Tags:");
+                        ui.add(TextEdit::singleline(&mut this.runtime_state.new_row_tags).hint_text("optional, comma separated"));
+                    });
                     ui.horizontal(|ui| {
                         ui.label("Fetched value:");
                         ui.add(
@@ -434,36 +1000,138 @@ impl ThisApp {
                         );
                         if add_button.clicked() {
                             this.add_new_row();
-                            this.selected_rows.push(false);
                             this.reset_new_row_fields();
                         }
                     });
                 });
+            if !open {
+                self.discard_draft_screenshot();
+            }
             self.runtime_state.show_add_row_dialog = open;
         }
     }
 
+    /// Fill the selector and transforms in from a preset, or clear the transforms for a custom selector.
+    fn select_preset(&mut self, preset: Option<usize>) {
+        self.runtime_state.new_row_preset = preset;
+        match preset.map(|index| &self.runtime_state.presets[index]) {
+            Some(preset) => {
+                let mut selectors = preset.selectors.iter();
+                self.runtime_state.new_row_css_selector =
+                    selectors.next().cloned().unwrap_or_default();
+                self.runtime_state.new_row_fallback_selectors =
+                    selectors.cloned().collect::<Vec<_>>().join("\n");
+                self.runtime_state.new_row_transforms = preset.transforms.clone();
+            }
+            None => self.runtime_state.new_row_transforms.clear(),
+        }
+    }
+
     fn add_new_row(&mut self) {
-        let cur_date_time = crate::get_current_date_time();
-        let new_row = ValueData {
-            id: Ulid::new().to_string(),
-            name: self.runtime_state.new_row_name.clone(),
-            link: self.runtime_state.new_row_link.clone(),
-            css_selector: self.runtime_state.new_row_css_selector.clone(),
-            previous_value: self.runtime_state.new_row_value.clone(),
-            latest_value: self.runtime_state.new_row_value.clone(),
-            last_updated: cur_date_time,
-        };
-        self.table_data.push(new_row);
+        let mut new_row = ValueData::new(
+            self.runtime_state.new_row_name.clone(),
+            self.runtime_state.new_row_link.clone(),
+            self.runtime_state.new_row_css_selector.clone(),
+            self.runtime_state.new_row_value.clone(),
+        );
+        new_row.on_change_command = self.runtime_state.new_row_on_change_command.clone();
+        new_row.group = self.runtime_state.new_row_group.trim().to_owned();
+        new_row.tags = group::parse_tags(&self.runtime_state.new_row_tags);
+        new_row.fingerprint = self.runtime_state.new_row_fingerprint.take();
+        new_row.transforms = self.runtime_state.new_row_transforms.clone();
+        new_row.browser = self.runtime_state.new_row_browser.clone();
+        new_row.session = self.runtime_state.new_row_session.clone();
+        new_row.steps = self.runtime_state.new_row_steps.clone();
+        new_row.script = self.runtime_state.new_row_script.clone();
+        new_row.fields = self.runtime_state.new_row_fields.clone();
+        new_row.fallback_selectors = parse_lines(&self.runtime_state.new_row_fallback_selectors);
+        new_row.mode = self.runtime_state.new_row_mode.clone();
+        new_row.matched_selector = self.runtime_state.new_row_matched_selector.take();
+        self.table_data.lock().unwrap().push(new_row);
+    }
+
+    /// Cookie import and login steps of the new row.
+    fn session_ui(&mut self, ui: &mut Ui) {
+        let state = &mut self.runtime_state;
+        ui.horizontal(|ui| {
+            ui.label("Cookies file:");
+            ui.add(
+                TextEdit::singleline(&mut state.new_row_cookies_file)
+                    .hint_text("JSON or cookies.txt exported from your browser"),
+            );
+            if ui
+                .add_enabled(
+                    !state.new_row_cookies_file.is_empty(),
+                    Button::new("Import"),
+                )
+                .clicked()
+            {
+                match crate::session::import_cookies(&state.new_row_cookies_file) {
+                    Ok(cookies) => {
+                        crate::session::merge_cookies(&mut state.new_row_session.cookies, cookies);
+                        state.session_error = None;
+                    }
+                    Err(error) => {
+                        state.session_error = Some(format!("Couldn't import cookies: {}", error))
+                    }
+                }
+            }
+            ui.label(format!("{} cookie(s)", state.new_row_session.cookies.len()));
+            if !state.new_row_session.cookies.is_empty() && ui.small_button("Clear").clicked() {
+                state.new_row_session.cookies.clear();
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Login page:");
+            ui.add(
+                TextEdit::singleline(&mut state.new_row_session.login_url)
+                    .hint_text("optional, steps run on the link otherwise"),
+            );
+        });
+        ui.horizontal(|ui| {
+            ui.label("Signed in when found:");
+            ui.add(
+                TextEdit::singleline(&mut state.new_row_session.logged_in_selector)
+                    .hint_text("optional, e.g. .account-menu, skips the steps"),
+            );
+        });
+        ui.label("Login steps:");
+        crate::steps::steps_ui(ui, "login_steps", &mut state.new_row_session.login_steps);
+        ui.horizontal(|ui| {
+            let recording = state.record_steps_promise.is_some();
+            let start_url = if state.new_row_session.login_url.is_empty() {
+                &state.new_row_link
+            } else {
+                &state.new_row_session.login_url
+            };
+            if ui
+                .add_enabled(!recording && !start_url.is_empty(), Button::new("⏺ Record"))
+                .on_hover_text("Sign in in the opened window, then close it")
+                .clicked()
+            {
+                state.session_error = None;
+                state.record_steps_promise = Some(crate::session::record_steps_promise(
+                    start_url.clone(),
+                    state.new_row_browser.clone(),
+                ));
+            }
+            if recording {
+                ui.spinner();
+                ui.label("close the browser window when signed in");
+            }
+        });
+        ui.colored_label(
+            Color32::YELLOW,
+            "Cookies and typed text, including passwords, are saved unencrypted with the app data.",
+        );
+        if let Some(error) = &state.session_error {
+            ui.colored_label(Color32::RED, error);
+        }
     }
 
     fn delete_confirmation_dialog(&mut self, ctx: &egui::Context) {
         if self.runtime_state.show_delete_confirmation_dialog {
-            let selected_count = self
-                .selected_rows
-                .iter()
-                .filter(|&&selected| selected)
-                .count();
+            let selected_count = self.runtime_state.selected_rows.len();
             egui::Window::new("Confirm Deletion")
                 .collapsible(false)
                 .resizable(false)
@@ -486,17 +1154,66 @@ impl ThisApp {
     }
 
     fn perform_delete(&mut self) {
-        let indices_to_remove: Vec<usize> = self
-            .selected_rows
-            .iter()
-            .enumerate()
-            .filter_map(|(i, &selected)| if selected { Some(i) } else { None })
-            .collect();
+        let selected_rows = std::mem::take(&mut self.runtime_state.selected_rows);
+        self.table_data.lock().unwrap().retain(|row| {
+            if selected_rows.contains(&row.id) {
+                crate::mqtt::remove_row(&row.id);
+                crate::screenshot::remove_row(&row.id);
+                false
+            } else {
+                true
+            }
+        });
+    }
 
-        for &index in indices_to_remove.iter().rev() {
-            if index < self.table_data.len() {
-                self.table_data.remove(index);
-                self.selected_rows.remove(index);
+    fn pause_dialog(&mut self, ctx: &egui::Context) {
+        if self.runtime_state.show_pause_dialog {
+            let mut open = self.runtime_state.show_pause_dialog;
+            let selected_count = self.runtime_state.selected_rows.len();
+            Window::new("Pause Rows")
+                .open(&mut open)
+                .collapsible(false)
+                .auto_sized()
+                .show(ctx, |ui| {
+                    ui.label(format!("Stop checking {} selected row(s).", selected_count));
+                    ui.horizontal(|ui| {
+                        ui.label("Until:");
+                        ui.add(
+                            TextEdit::singleline(&mut self.runtime_state.pause_until)
+                                .hint_text("YYYY-MM-DD HH:MM, empty to pause until resumed"),
+                        );
+                    });
+                    let until = self.runtime_state.pause_until.trim();
+                    let paused_until = if until.is_empty() {
+                        Ok(None)
+                    } else {
+                        NaiveDateTime::parse_from_str(until, "%Y-%m-%d %H:%M")
+                            .ok()
+                            .and_then(|until| Local.from_local_datetime(&until).earliest())
+                            .map(|until| Some(until.with_timezone(&Utc)))
+                            .ok_or(())
+                    };
+                    if paused_until.is_err() {
+                        ui.colored_label(Color32::RED, "Enter a date like 2024-12-31 18:00.");
+                    }
+                    if ui
+                        .add_enabled(paused_until.is_ok(), Button::new("Pause"))
+                        .clicked()
+                    {
+                        self.set_selected_rows_paused(true, paused_until.unwrap_or_default());
+                        self.runtime_state.show_pause_dialog = false;
+                    }
+                });
+            self.runtime_state.show_pause_dialog &= open;
+        }
+    }
+
+    fn set_selected_rows_paused(&mut self, paused: bool, until: Option<DateTime<Utc>>) {
+        let selected_rows = &self.runtime_state.selected_rows;
+        for row in self.table_data.lock().unwrap().iter_mut() {
+            if selected_rows.contains(&row.id) {
+                row.enabled = !paused;
+                row.paused_until = until;
             }
         }
     }
@@ -526,59 +1243,848 @@ impl ThisApp {
         }
     }
 
-    fn update_value(&mut self, id: String, value: String) {
-        println!("Updating value for ID: {}, Value: {}", id, value);
-        if let Some(index) = self.table_data.iter().position(|row| row.id == id) {
-            let row = &mut self.table_data[index];
-            row.previous_value = row.latest_value.clone();
-            row.latest_value = value;
-            row.last_updated = crate::get_current_date_time();
+    fn notification_settings_dialog(&mut self, ctx: &egui::Context) {
+        if self.runtime_state.show_notification_settings_dialog {
+            let mut open = self.runtime_state.show_notification_settings_dialog;
+            Window::new("Notification Settings")
+                .open(&mut open)
+                .collapsible(false)
+                .auto_sized()
+                .show(ctx, |ui| {
+                    let settings = &mut self.notification_settings;
+                    ui.strong("Quiet hours");
+                    quiet_hours_ui(ui, "All channels", &mut settings.quiet_hours);
+                    for channel_settings in &mut settings.channels {
+                        ui.horizontal(|ui| {
+                            ui.checkbox(
+                                &mut channel_settings.enabled,
+                                format!("{} notifications", channel_settings.channel.as_str()),
+                            );
+                        });
+                        quiet_hours_ui(
+                            ui,
+                            channel_settings.channel.as_str(),
+                            &mut channel_settings.quiet_hours,
+                        );
+                    }
+                    ui.label("Changes during quiet hours are delivered when they end, even after a restart.");
+
+                    ui.separator();
+                    ui.strong("Rate limiting");
+                    ui.horizontal(|ui| {
+                        ui.label("Notify about the same row at most every");
+                        ui.add(
+                            egui::DragValue::new(&mut settings.cooldown_minutes)
+                                .range(0..=i64::MAX),
+                        );
+                        ui.label("minutes");
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Summarize when more than");
+                        ui.add(
+                            egui::DragValue::new(&mut settings.max_per_run).range(0..=usize::MAX),
+                        );
+                        ui.label("rows change at once (0 = never)");
+                    });
+                    ui.colored_label(Color32::RED, "* Restart the app to apply changes.");
+                });
+            self.runtime_state.show_notification_settings_dialog = open;
+        }
+    }
+
+    fn digest_settings_dialog(&mut self, ctx: &egui::Context) {
+        if self.runtime_state.show_digest_settings_dialog {
+            let mut open = self.runtime_state.show_digest_settings_dialog;
+            Window::new("Digest Report")
+                .open(&mut open)
+                .collapsible(false)
+                .auto_sized()
+                .show(ctx, |ui| {
+                    let settings = &mut self.digest_settings;
+                    ui.checkbox(&mut settings.enabled, "Send a digest of all tracked rows");
+                    ui.add_enabled_ui(settings.enabled, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label("Schedule:");
+                            ui.add(
+                                TextEdit::singleline(&mut settings.schedule)
+                                    .hint_text("sec min hour day month weekday"),
+                            );
+                            ui.hyperlink_to("what?", "https://crontab.guru/");
+                        });
+                        ui.label("e.g. \"0 0 8 * * *\" daily at 08:00, \"0 0 8 * * Mon\" weekly.");
+                        ui.horizontal(|ui| {
+                            ui.checkbox(&mut settings.notify, "Send through");
+                            egui::ComboBox::from_id_salt("digest_channel")
+                                .selected_text(settings.channel.as_str())
+                                .show_ui(ui, |ui| {
+                                    for channel in Channel::get_channels() {
+                                        ui.selectable_value(
+                                            &mut settings.channel,
+                                            channel,
+                                            channel.as_str(),
+                                        );
+                                    }
+                                });
+                        });
+                        ui.horizontal(|ui| {
+                            ui.checkbox(&mut settings.write_to_file, "Write to file:");
+                            ui.add(
+                                TextEdit::singleline(&mut settings.file_path)
+                                    .hint_text("e.g. /home/me/digest.html"),
+                            );
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("File format:");
+                            egui::ComboBox::from_id_salt("digest_format")
+                                .selected_text(settings.format.as_str())
+                                .show_ui(ui, |ui| {
+                                    for format in DigestFormat::get_formats() {
+                                        ui.selectable_value(
+                                            &mut settings.format,
+                                            format,
+                                            format.as_str(),
+                                        );
+                                    }
+                                });
+                        });
+                    });
+                    ui.colored_label(Color32::RED, "* Restart the app to apply changes.");
+                });
+            self.runtime_state.show_digest_settings_dialog = open;
+        }
+    }
+
+    fn api_settings_dialog(&mut self, ctx: &egui::Context) {
+        if self.runtime_state.show_api_settings_dialog {
+            let mut open = self.runtime_state.show_api_settings_dialog;
+            Window::new("Local API")
+                .open(&mut open)
+                .collapsible(false)
+                .auto_sized()
+                .show(ctx, |ui| {
+                    let settings = &mut self.api_settings;
+                    ui.checkbox(&mut settings.enabled, "Serve a JSON API on localhost");
+                    ui.add_enabled_ui(settings.enabled, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label("Port:");
+                            ui.add(egui::DragValue::new(&mut settings.port).range(1..=u16::MAX));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Token:");
+                            ui.add(TextEdit::singleline(&mut settings.token).interactive(false));
+                            if ui.button("📋").on_hover_text("Copy").clicked() {
+                                ui.ctx().copy_text(settings.token.clone());
+                            }
+                            if ui.button("Regenerate").clicked() {
+                                settings.token = Ulid::new().to_string();
+                            }
+                        });
+                        ui.label("Send it as \"Authorization: Bearer <token>\".");
+                        ui.checkbox(
+                            &mut settings.allow_commands,
+                            "Allow setting commands, browser arguments and profiles, scripts and steps",
+                        );
+                        if settings.allow_commands {
+                            ui.colored_label(
+                                Color32::YELLOW,
+                                "Anyone with the token can then run shell commands as you and use your browser profiles.",
+                            );
+                        }
+                    });
+
+                    ui.separator();
+                    let settings = &mut self.metrics_settings;
+                    ui.checkbox(
+                        &mut settings.enabled,
+                        "Serve Prometheus metrics on /metrics",
+                    );
+                    ui.add_enabled_ui(settings.enabled, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label("Port:");
+                            ui.add(egui::DragValue::new(&mut settings.port).range(1..=u16::MAX));
+                        });
+                    });
+                    ui.colored_label(Color32::RED, "* Restart the app to apply changes.");
+                });
+            self.runtime_state.show_api_settings_dialog = open;
+        }
+    }
+
+    fn mqtt_settings_dialog(&mut self, ctx: &egui::Context) {
+        if self.runtime_state.show_mqtt_settings_dialog {
+            let mut open = self.runtime_state.show_mqtt_settings_dialog;
+            Window::new("MQTT")
+                .open(&mut open)
+                .collapsible(false)
+                .auto_sized()
+                .show(ctx, |ui| {
+                    let settings = &mut self.mqtt_settings;
+                    ui.checkbox(&mut settings.enabled, "Publish values to an MQTT broker");
+                    ui.add_enabled_ui(settings.enabled, |ui| {
+                        egui::Grid::new("mqtt_settings_grid").show(ui, |ui| {
+                            ui.label("Host:");
+                            ui.text_edit_singleline(&mut settings.host);
+                            ui.end_row();
+                            ui.label("Port:");
+                            ui.add(egui::DragValue::new(&mut settings.port).range(1..=u16::MAX));
+                            ui.end_row();
+                            ui.label("Username:");
+                            ui.add(
+                                TextEdit::singleline(&mut settings.username).hint_text("optional"),
+                            );
+                            ui.end_row();
+                            ui.label("Password:");
+                            ui.add(TextEdit::singleline(&mut settings.password).password(true));
+                            ui.end_row();
+                            ui.label("Client ID:");
+                            ui.text_edit_singleline(&mut settings.client_id);
+                            ui.end_row();
+                            ui.label("Topic prefix:");
+                            ui.text_edit_singleline(&mut settings.topic_prefix);
+                            ui.end_row();
+                            ui.label("QoS:");
+                            ui.add(egui::DragValue::new(&mut settings.qos).range(0..=2));
+                            ui.end_row();
+                        });
+                        ui.checkbox(&mut settings.discovery, "Home Assistant discovery");
+                        ui.add_enabled_ui(settings.discovery, |ui| {
+                            ui.horizontal(|ui| {
+                                ui.label("Discovery prefix:");
+                                ui.text_edit_singleline(&mut settings.discovery_prefix);
+                            });
+                        });
+                        ui.label(format!(
+                            "Values go to {0}/<id>/state, changes to {0}/<id>/change.",
+                            settings.topic_prefix
+                        ));
+                    });
+                    ui.colored_label(Color32::RED, "* Restart the app to apply changes.");
+                });
+            self.runtime_state.show_mqtt_settings_dialog = open;
+        }
+    }
+
+    fn time_display_dialog(&mut self, ctx: &egui::Context) {
+        if self.runtime_state.show_time_display_dialog {
+            let mut open = self.runtime_state.show_time_display_dialog;
+            Window::new("Time Display")
+                .open(&mut open)
+                .collapsible(false)
+                .auto_sized()
+                .show(ctx, |ui| {
+                    let settings = &mut self.time_display_settings;
+                    ui.checkbox(
+                        &mut settings.relative,
+                        "Show relative times (\"5 min ago\")",
+                    );
+                    ui.horizontal(|ui| {
+                        ui.label("Format:");
+                        ui.add(
+                            TextEdit::singleline(&mut settings.format)
+                                .hint_text("e.g. %Y-%m-%d %H:%M"),
+                        );
+                        ui.hyperlink_to(
+                            "what?",
+                            "https://docs.rs/chrono/latest/chrono/format/strftime/index.html",
+                        );
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Timezone:");
+                        egui::ComboBox::from_id_salt("display_timezone")
+                            .selected_text(settings.timezone.as_str())
+                            .show_ui(ui, |ui| {
+                                for timezone in DisplayTimezone::get_timezones() {
+                                    ui.selectable_value(
+                                        &mut settings.timezone,
+                                        timezone,
+                                        timezone.as_str(),
+                                    );
+                                }
+                            });
+                        if settings.timezone == DisplayTimezone::FixedOffset {
+                            ui.label("UTC +");
+                            ui.add(
+                                egui::DragValue::new(&mut settings.offset_minutes)
+                                    .range(-24 * 60 + 1..=24 * 60 - 1),
+                            );
+                            ui.label("minutes");
+                        }
+                    });
+                    ui.label(format!(
+                        "Preview: {}",
+                        settings.format_absolute(&Utc::now())
+                    ));
+                });
+            self.runtime_state.show_time_display_dialog = open;
         }
     }
 
+    fn browser_settings_dialog(&mut self, ctx: &egui::Context) {
+        if self.runtime_state.show_browser_settings_dialog {
+            let mut open = self.runtime_state.show_browser_settings_dialog;
+            Window::new("Browser")
+                .open(&mut open)
+                .collapsible(false)
+                .auto_sized()
+                .show(ctx, |ui| {
+                    let settings = &mut self.browser_settings;
+                    egui::Grid::new("browser_settings_grid").show(ui, |ui| {
+                        ui.label("Chrome binary:");
+                        ui.add(
+                            TextEdit::singleline(&mut settings.chrome_path)
+                                .hint_text("empty to find it automatically"),
+                        );
+                        ui.end_row();
+                        ui.label("User agent:");
+                        ui.add(
+                            TextEdit::singleline(&mut settings.user_agent)
+                                .hint_text("empty for Chrome's own"),
+                        );
+                        ui.end_row();
+                        ui.label("Locale:");
+                        ui.add(
+                            TextEdit::singleline(&mut settings.locale)
+                                .hint_text("e.g. en-US, sets Accept-Language"),
+                        );
+                        ui.end_row();
+                        ui.label("Window size:");
+                        ui.horizontal(|ui| {
+                            ui.add(egui::DragValue::new(&mut settings.window_width));
+                            ui.label("x");
+                            ui.add(egui::DragValue::new(&mut settings.window_height));
+                            ui.label("(0 for default)");
+                        });
+                        ui.end_row();
+                        ui.label("Proxy server:");
+                        ui.add(
+                            TextEdit::singleline(&mut settings.proxy_server)
+                                .hint_text("e.g. http://proxy:3128"),
+                        );
+                        ui.end_row();
+                        ui.label("Extra arguments:");
+                        ui.add(
+                            TextEdit::multiline(&mut settings.extra_args)
+                                .desired_rows(2)
+                                .hint_text("one per line, e.g. --disable-extensions"),
+                        );
+                        ui.end_row();
+                        ui.label("Profile directory:");
+                        ui.add(
+                            TextEdit::singleline(&mut settings.profile_dir)
+                                .hint_text("empty for a fresh profile every fetch"),
+                        )
+                        .on_hover_text(
+                            "Keeps logins and site data between fetches. Chrome opens a \
+                             profile only once, so rows sharing it are fetched one at a time.",
+                        );
+                        ui.end_row();
+                        ui.label("Page load timeout:");
+                        ui.add(
+                            egui::DragValue::new(&mut settings.navigation_timeout_secs)
+                                .range(1..=600)
+                                .suffix(" s"),
+                        );
+                        ui.end_row();
+                        ui.label("Element timeout:");
+                        ui.add(
+                            egui::DragValue::new(&mut settings.element_timeout_secs)
+                                .range(1..=600)
+                                .suffix(" s"),
+                        );
+                        ui.end_row();
+                        ui.label("Read value when:");
+                        text_condition_ui(ui, "global_wait_for_text", &mut settings.wait_for_text);
+                        ui.end_row();
+                    });
+                    ui.checkbox(&mut settings.wait_for_network_idle, "Wait for network idle")
+                        .on_hover_text("After loading, wait until the page stops making requests");
+                    ui.checkbox(&mut settings.sandbox, "Sandbox")
+                        .on_hover_text("Turn off to run Chrome as root, e.g. in a container");
+                    ui.label("Rows can override everything except the Chrome binary, window size and sandbox.");
+                });
+            // fetches read the settings when they start, so changes apply right away
+            crate::browser::set_global_settings(&self.browser_settings);
+            self.runtime_state.show_browser_settings_dialog = open;
+        }
+    }
+
+    fn screenshot_settings_dialog(&mut self, ctx: &egui::Context) {
+        if self.runtime_state.show_screenshot_settings_dialog {
+            let mut open = self.runtime_state.show_screenshot_settings_dialog;
+            Window::new("Screenshots")
+                .open(&mut open)
+                .collapsible(false)
+                .auto_sized()
+                .show(ctx, |ui| {
+                    let settings = &mut self.screenshot_settings;
+                    ui.checkbox(&mut settings.on_change, "When a value changes");
+                    ui.checkbox(&mut settings.on_failure, "When a fetch fails");
+                    ui.horizontal(|ui| {
+                        ui.label("Capture:");
+                        ui.radio_value(&mut settings.full_page, false, "Matched element");
+                        ui.radio_value(&mut settings.full_page, true, "Whole page");
+                    });
+                    ui.label("Failures always capture the whole page.");
+                    if let Some(dir) = crate::screenshot::screenshots_dir() {
+                        ui.label(format!("Saved in {}", dir.display()));
+                    }
+                });
+            crate::screenshot::set_global_settings(&self.screenshot_settings);
+            self.runtime_state.show_screenshot_settings_dialog = open;
+        }
+    }
+
+    fn inbox_dialog(&mut self, ctx: &egui::Context) {
+        if self.runtime_state.show_inbox_dialog {
+            let mut open = self.runtime_state.show_inbox_dialog;
+            Window::new("Inbox")
+                .open(&mut open)
+                .collapsible(false)
+                .default_size([500.0, 400.0])
+                .show(ctx, |ui| {
+                    let mut state = self.notifier_state.lock().unwrap();
+                    if state.inbox().is_empty() {
+                        ui.label("No notifications yet.");
+                        return;
+                    }
+                    if ui.button("Clear").clicked() {
+                        state.clear_inbox();
+                        return;
+                    }
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        egui::Grid::new("inbox_grid").striped(true).show(ui, |ui| {
+                            for entry in state.inbox().iter().rev() {
+                                ui.label(self.time_display_settings.format(&entry.changed_at))
+                                    .on_hover_text(
+                                        self.time_display_settings
+                                            .format_absolute(&entry.changed_at),
+                                    );
+                                ui.label(&entry.name);
+                                ui.label(&entry.change);
+                                // older screenshots are removed with the history they belong to
+                                match &entry.screenshot {
+                                    Some(path) if std::path::Path::new(path).exists() => {
+                                        if ui
+                                            .small_button("📷")
+                                            .on_hover_text("What the page looked like")
+                                            .clicked()
+                                        {
+                                            self.runtime_state.viewed_screenshot =
+                                                Some(path.clone());
+                                        }
+                                    }
+                                    _ => {
+                                        ui.label("");
+                                    }
+                                }
+                                ui.end_row();
+                            }
+                        });
+                    });
+                });
+            self.runtime_state.show_inbox_dialog = open;
+        }
+    }
+
+    fn screenshot_viewer(&mut self, ctx: &egui::Context) {
+        if let Some(path) = &self.runtime_state.viewed_screenshot {
+            let mut open = true;
+            Window::new("Screenshot")
+                .open(&mut open)
+                .collapsible(false)
+                .default_size([800.0, 600.0])
+                .show(ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label(path);
+                        if ui.small_button("Open").clicked() {
+                            ui.ctx()
+                                .open_url(egui::OpenUrl::new_tab(format!("file://{}", path)));
+                        }
+                    });
+                    egui::ScrollArea::both().show(ui, |ui| {
+                        ui.add(
+                            egui::Image::new(format!("file://{}", path)).fit_to_original_size(1.0),
+                        );
+                    });
+                });
+            if !open {
+                self.runtime_state.viewed_screenshot = None;
+            }
+        }
+    }
+
+    fn diff_viewer(&mut self, ctx: &egui::Context) {
+        let Some(id) = &self.runtime_state.viewed_diff else {
+            return;
+        };
+        let Some(name) = self
+            .table_data
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|row| row.id == *id)
+            .map(|row| {
+                let cached = self.runtime_state.viewed_diff_lines.as_ref().is_some_and(
+                    |(cached_id, updated, _)| cached_id == id && *updated == row.last_updated,
+                );
+                if !cached {
+                    self.runtime_state.viewed_diff_lines = Some((
+                        id.clone(),
+                        row.last_updated,
+                        crate::diff::line_diff(&row.previous_value, &row.latest_value),
+                    ));
+                }
+                row.name.clone()
+            })
+        else {
+            self.runtime_state.viewed_diff = None;
+            self.runtime_state.viewed_diff_lines = None;
+            return;
+        };
+        let Some((_, _, diff)) = &self.runtime_state.viewed_diff_lines else {
+            return;
+        };
+        let mut open = true;
+        Window::new(format!("Changes in {}", name))
+            .open(&mut open)
+            .collapsible(false)
+            .default_size([600.0, 400.0])
+            .show(ctx, |ui| {
+                egui::ScrollArea::both().show(ui, |ui| {
+                    let near_change = |index: usize| {
+                        let start = index.saturating_sub(crate::diff::CONTEXT_LINES);
+                        let end = (index + crate::diff::CONTEXT_LINES + 1).min(diff.len());
+                        diff[start..end]
+                            .iter()
+                            .any(|line| !matches!(line, DiffLine::Same(_)))
+                    };
+                    let mut skipped = false;
+                    for (index, line) in diff.iter().enumerate() {
+                        let (prefix, text, color) = match line {
+                            DiffLine::Same(_) if !near_change(index) => {
+                                if !skipped {
+                                    ui.weak("…");
+                                    skipped = true;
+                                }
+                                continue;
+                            }
+                            DiffLine::Same(text) => (" ", text, ui.visuals().text_color()),
+                            DiffLine::Added(text) => ("+", text, Color32::GREEN),
+                            DiffLine::Removed(text) => ("-", text, Color32::RED),
+                        };
+                        skipped = false;
+                        ui.colored_label(
+                            color,
+                            egui::RichText::new(format!("{} {}", prefix, text)).monospace(),
+                        );
+                    }
+                });
+            });
+        if !open {
+            self.runtime_state.viewed_diff = None;
+            self.runtime_state.viewed_diff_lines = None;
+        }
+    }
+
+    fn load_error_dialog(&mut self, ctx: &egui::Context) {
+        if let Some(error) = &self.runtime_state.load_error {
+            let mut close = false;
+            Window::new("Couldn't Load Saved Data")
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.label(error);
+                    if self.runtime_state.skip_save {
+                        ui.colored_label(
+                            Color32::RED,
+                            "Changes made now won't be saved, so the old data stays intact.",
+                        );
+                    }
+                    if ui.button("OK").clicked() {
+                        close = true;
+                    }
+                });
+            if close {
+                self.runtime_state.load_error = None;
+            }
+        }
+    }
+
+    fn update_value(&mut self, id: String, result: crate::FetchResult) {
+        println!("Updating value for ID: {}, Value: {}", id, result.value);
+        let mut table_data = self.table_data.lock().unwrap();
+        if let Some(index) = table_data.iter().position(|row| row.id == id) {
+            crate::apply_new_value(&mut table_data[index], result);
+        }
+        crate::update_computed_rows(&mut table_data);
+    }
+
+    /// Fetch every row except those in paused groups.
     fn fetch_latest_values(&mut self) {
+        let rows: Vec<ValueData> = {
+            let groups = self.groups.lock().unwrap();
+            self.table_data
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|row| {
+                    !groups
+                        .iter()
+                        .any(|group| group.paused && group.name == row.group)
+                })
+                .cloned()
+                .collect()
+        };
+        self.fetch_rows(&rows);
+    }
+
+    fn fetch_rows(&mut self, rows: &[ValueData]) {
+        // computed rows have nothing to load, bring them up to date right away
+        crate::update_computed_rows(&mut self.table_data.lock().unwrap());
         self.runtime_state.fetching_latest_values = true;
-        self.runtime_state.fetch_latest_values_promises =
-            crate::fetch_latest_values_promises(&self.table_data);
+        self.runtime_state.fetch_latest_values_promises = crate::fetch_latest_values_promises(rows);
     }
 
     fn sheduled_job(&mut self, ctx: &egui::Context) -> thread::JoinHandle<()> {
         println!("sheduled_job called");
         let ctx = ctx.clone();
-        let mut table_data = self.table_data.clone();
-        let sender = self.runtime_state.mpsc_sender.clone();
+        let digest_table_data = self.table_data.clone();
         let custom_time_interval = self.custom_time_interval;
         println!("custom_time_interval: {}", custom_time_interval);
+        // groups with their own interval get a job each, everything else uses the global one
+        let mut group_intervals: Vec<u64> = self
+            .groups
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|group| group.interval_minutes)
+            .filter(|&interval| interval != 0 && interval != custom_time_interval)
+            .collect();
+        group_intervals.sort_unstable();
+        group_intervals.dedup();
+        let mut fetch_jobs = Vec::new();
+        let notifier = Arc::new(Mutex::new(Notifier::new(
+            self.notification_settings.clone(),
+            self.notifier_state.clone(),
+        )));
+        for job_interval in std::iter::once(custom_time_interval).chain(group_intervals.clone()) {
+            let ctx = ctx.clone();
+            let table_data = self.table_data.clone();
+            let groups = self.groups.clone();
+            let group_intervals = group_intervals.clone();
+            let notifier = notifier.clone();
+            let job =
+                Job::new_repeated(Duration::from_secs(job_interval * 60), move |_uuid, _l| {
+                    // flag.store(true, Ordering::SeqCst);
+                    println!(
+                        "sheduled_job: flag set at {}",
+                        crate::get_current_date_time()
+                    );
+                    crate::fetch_latest_values_and_notify_blocking(&table_data, &notifier, |row| {
+                        match group::row_interval(&groups, row, custom_time_interval) {
+                            Some(interval) if interval == job_interval => true,
+                            // an interval set after startup runs with the global job until restart
+                            Some(interval) => {
+                                job_interval == custom_time_interval
+                                    && !group_intervals.contains(&interval)
+                            }
+                            None => false,
+                        }
+                    });
+                    ctx.request_repaint();
+                })
+                .unwrap();
+            fetch_jobs.push(job);
+        }
+        let deferred_notifier = notifier.clone();
+        let digest_notifier = notifier.clone();
+        let digest_settings = self.digest_settings.clone();
+        let mut digest = Digest::new(
+            digest_settings.clone(),
+            self.digest_state.clone(),
+            &self.table_data.lock().unwrap(),
+        );
 
         thread::spawn(move || {
             let rt = tokio::runtime::Runtime::new().unwrap();
             rt.block_on(async {
                 let jobs_scheduler = JobScheduler::new().await.unwrap();
+                for job in fetch_jobs {
+                    jobs_scheduler.add(job).await.unwrap();
+                }
                 jobs_scheduler
                     .add(
                         Job::new_repeated(
-                            Duration::from_secs(custom_time_interval * 60),
+                            Duration::from_secs(DEFERRED_NOTIFICATIONS_CHECK_INTERVAL),
                             move |_uuid, _l| {
-                                // flag.store(true, Ordering::SeqCst);
-                                println!(
-                                    "sheduled_job: flag set at {}",
-                                    crate::get_current_date_time()
-                                );
-                                let new_values =
-                                    crate::fetch_latest_values_and_notify_blocking(&mut table_data);
-                                if let Err(e) = sender.send(new_values) {
-                                    eprintln!("Failed to send new_values: {:?}", e);
-                                }
-                                ctx.request_repaint();
+                                deferred_notifier
+                                    .lock()
+                                    .unwrap()
+                                    .flush(chrono::Local::now());
                             },
                         )
                         .unwrap(),
                     )
                     .await
                     .unwrap();
+                if digest_settings.enabled {
+                    match Job::new_tz(
+                        digest_settings.schedule.as_str(),
+                        chrono::Local,
+                        move |_uuid, _l| {
+                            println!("sending digest at {}", crate::get_current_date_time());
+                            // a copy, so the table isn't locked while the digest is delivered
+                            let rows = digest_table_data.lock().unwrap().clone();
+                            digest.send(
+                                &rows,
+                                &mut digest_notifier.lock().unwrap(),
+                                chrono::Local::now(),
+                            );
+                        },
+                    ) {
+                        Ok(job) => {
+                            jobs_scheduler.add(job).await.unwrap();
+                        }
+                        Err(e) => eprintln!(
+                            "Invalid digest schedule {:?}: {:?}",
+                            digest_settings.schedule, e
+                        ),
+                    }
+                }
                 jobs_scheduler.start().await.unwrap();
                 tokio::signal::ctrl_c().await.unwrap();
             });
         })
     }
 }
+
+fn browser_overrides_ui(ui: &mut Ui, overrides: &mut BrowserOverrides) {
+    egui::Grid::new("browser_overrides_grid").show(ui, |ui| {
+        ui.label("User agent:");
+        ui.add(
+            TextEdit::singleline(&mut overrides.user_agent).hint_text("empty for the global one"),
+        );
+        ui.end_row();
+        ui.label("Locale:");
+        ui.add(
+            TextEdit::singleline(&mut overrides.locale).hint_text("e.g. de-DE for prices in euros"),
+        );
+        ui.end_row();
+        ui.label("Proxy server:");
+        ui.add(
+            TextEdit::singleline(&mut overrides.proxy_server).hint_text("empty for the global one"),
+        );
+        ui.end_row();
+        ui.label("Extra arguments:");
+        ui.add(
+            TextEdit::multiline(&mut overrides.extra_args)
+                .desired_rows(1)
+                .hint_text("added to the global ones, one per line"),
+        );
+        ui.end_row();
+        ui.label("Profile directory:");
+        ui.add(
+            TextEdit::singleline(&mut overrides.profile_dir)
+                .hint_text("e.g. a profile signed in to this site"),
+        )
+        .on_hover_text("Rows sharing a profile are fetched one at a time");
+        ui.end_row();
+        ui.label("Page load timeout:");
+        ui.add(
+            egui::DragValue::new(&mut overrides.navigation_timeout_secs)
+                .range(0..=600)
+                .suffix(" s"),
+        )
+        .on_hover_text("0 for the global one");
+        ui.end_row();
+        ui.label("Element timeout:");
+        ui.add(
+            egui::DragValue::new(&mut overrides.element_timeout_secs)
+                .range(0..=600)
+                .suffix(" s"),
+        )
+        .on_hover_text("0 for the global one");
+        ui.end_row();
+        ui.label("Wait for network idle:");
+        let network_idle_text = match overrides.wait_for_network_idle {
+            None => "Global",
+            Some(true) => "Yes",
+            Some(false) => "No",
+        };
+        egui::ComboBox::from_id_salt("row_wait_for_network_idle")
+            .selected_text(network_idle_text)
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut overrides.wait_for_network_idle, None, "Global");
+                ui.selectable_value(&mut overrides.wait_for_network_idle, Some(true), "Yes");
+                ui.selectable_value(&mut overrides.wait_for_network_idle, Some(false), "No");
+            });
+        ui.end_row();
+        ui.label("Read value when:");
+        ui.horizontal(|ui| {
+            let mut inherit = overrides.wait_for_text.is_none();
+            ui.checkbox(&mut inherit, "Global");
+            if inherit {
+                overrides.wait_for_text = None;
+            } else {
+                let condition = overrides.wait_for_text.get_or_insert_with(Default::default);
+                text_condition_ui(ui, "row_wait_for_text", condition);
+            }
+        });
+        ui.end_row();
+    });
+}
+
+/// When to accept the text read from the page, with the pattern for `Matches`.
+fn text_condition_ui(ui: &mut Ui, id_salt: &str, condition: &mut TextCondition) {
+    ui.horizontal(|ui| {
+        egui::ComboBox::from_id_salt(id_salt)
+            .selected_text(condition.as_str())
+            .show_ui(ui, |ui| {
+                for option in TextCondition::get_conditions() {
+                    let selected =
+                        std::mem::discriminant(condition) == std::mem::discriminant(&option);
+                    if ui.selectable_label(selected, option.as_str()).clicked() && !selected {
+                        *condition = option;
+                    }
+                }
+            });
+        if let TextCondition::Matches(pattern) = condition {
+            ui.add(TextEdit::singleline(pattern).hint_text("regex, e.g. \\d"));
+        }
+        if let Err(error) = condition.compile() {
+            ui.colored_label(egui::Color32::RED, error);
+        }
+    });
+}
+
+/// Non-empty trimmed lines of `text`.
+fn parse_lines(text: &str) -> Vec<String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_owned)
+        .collect()
+}
+
+fn quiet_hours_ui(ui: &mut Ui, label: &str, quiet_hours: &mut QuietHours) {
+    ui.horizontal(|ui| {
+        ui.checkbox(&mut quiet_hours.enabled, label);
+        ui.add_enabled_ui(quiet_hours.enabled, |ui| {
+            ui.label("from");
+            time_ui(ui, &mut quiet_hours.start);
+            ui.label("to");
+            time_ui(ui, &mut quiet_hours.end);
+        });
+    });
+}
+
+fn time_ui(ui: &mut Ui, time: &mut NaiveTime) {
+    let mut hour = time.hour();
+    let mut minute = time.minute();
+    ui.add(egui::DragValue::new(&mut hour).range(0..=23));
+    ui.label(":");
+    ui.add(egui::DragValue::new(&mut minute).range(0..=59));
+    if let Some(new_time) = NaiveTime::from_hms_opt(hour, minute, 0) {
+        *time = new_time;
+    }
+}
@@ -0,0 +1,173 @@
+use egui::{Button, TextEdit, Window};
+use std::sync::{Arc, Mutex};
+
+use super::{ThisApp, ValueData};
+
+/// Group settings shared between the UI and the scheduled jobs.
+pub type SharedGroups = Arc<Mutex<Vec<RowGroup>>>;
+
+/// Settings of all rows whose `ValueData::group` has this name.
+/// The empty name is the group of ungrouped rows.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Default)]
+#[serde(default)]
+pub struct RowGroup {
+    pub name: String,
+    pub collapsed: bool,
+    /// Paused groups are skipped by scheduled and "fetch all" checks.
+    pub paused: bool,
+    /// Check interval in minutes, 0 to use the global one.
+    pub interval_minutes: u64,
+}
+
+impl RowGroup {
+    pub fn display_name(&self) -> &str {
+        if self.name.is_empty() {
+            "Ungrouped"
+        } else {
+            &self.name
+        }
+    }
+}
+
+/// Settings of the group called `name`, created with defaults on first use.
+pub fn get_or_insert<'a>(groups: &'a mut Vec<RowGroup>, name: &str) -> &'a mut RowGroup {
+    match groups.iter().position(|group| group.name == name) {
+        Some(index) => &mut groups[index],
+        None => {
+            groups.push(RowGroup {
+                name: name.to_owned(),
+                ..Default::default()
+            });
+            groups.last_mut().unwrap()
+        }
+    }
+}
+
+/// Minutes between scheduled checks of a row, or `None` while its group is paused.
+pub fn row_interval(groups: &SharedGroups, row: &ValueData, default_minutes: u64) -> Option<u64> {
+    let groups = groups.lock().unwrap();
+    match groups.iter().find(|group| group.name == row.group) {
+        Some(group) if group.paused => None,
+        Some(group) if group.interval_minutes != 0 => Some(group.interval_minutes),
+        _ => Some(default_minutes),
+    }
+}
+
+/// Split comma separated tags, dropping blanks and duplicates.
+pub fn parse_tags(text: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in text.split(',').map(str::trim) {
+        if !tag.is_empty() && !tags.iter().any(|existing| existing == tag) {
+            tags.push(tag.to_owned());
+        }
+    }
+    tags
+}
+
+impl ThisApp {
+    pub(super) fn group_tags_dialog(&mut self, ctx: &egui::Context) {
+        if self.runtime_state.show_group_tags_dialog {
+            let mut open = self.runtime_state.show_group_tags_dialog;
+            let selected_count = self.runtime_state.selected_rows.len();
+            Window::new("Group and Tag Rows")
+                .open(&mut open)
+                .collapsible(false)
+                .auto_sized()
+                .show(ctx, |ui| {
+                    ui.label(format!("{} selected row(s)", selected_count));
+                    ui.horizontal(|ui| {
+                        ui.label("Group:");
+                        ui.add(
+                            TextEdit::singleline(&mut self.runtime_state.bulk_group)
+                                .hint_text("empty for ungrouped"),
+                        );
+                        if ui
+                            .add_enabled(selected_count > 0, Button::new("Move"))
+                            .clicked()
+                        {
+                            let group = self.runtime_state.bulk_group.trim().to_owned();
+                            self.edit_selected_rows(|row| row.group = group.clone());
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Tags:");
+                        ui.add(
+                            TextEdit::singleline(&mut self.runtime_state.bulk_tags)
+                                .hint_text("comma separated"),
+                        );
+                        let tags = parse_tags(&self.runtime_state.bulk_tags);
+                        let enabled = selected_count > 0 && !tags.is_empty();
+                        if ui.add_enabled(enabled, Button::new("Add")).clicked() {
+                            self.edit_selected_rows(|row| {
+                                for tag in &tags {
+                                    if !row.tags.contains(tag) {
+                                        row.tags.push(tag.clone());
+                                    }
+                                }
+                            });
+                        }
+                        if ui.add_enabled(enabled, Button::new("Remove")).clicked() {
+                            self.edit_selected_rows(|row| {
+                                row.tags.retain(|tag| !tags.contains(tag))
+                            });
+                        }
+                    });
+                });
+            self.runtime_state.show_group_tags_dialog = open;
+        }
+    }
+
+    fn edit_selected_rows(&mut self, mut edit: impl FnMut(&mut ValueData)) {
+        let selected_rows = &self.runtime_state.selected_rows;
+        for row in self.table_data.lock().unwrap().iter_mut() {
+            if selected_rows.contains(&row.id) {
+                edit(row);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row_in(group: &str) -> ValueData {
+        let mut row = ValueData::new(
+            "Book".to_owned(),
+            "https://example.com".to_owned(),
+            ".price".to_owned(),
+            "$10".to_owned(),
+        );
+        row.group = group.to_owned();
+        row
+    }
+
+    #[test]
+    fn tags_are_trimmed_and_unique() {
+        assert_eq!(parse_tags(" books, gifts ,,books, "), ["books", "gifts"]);
+        assert!(parse_tags(" , ").is_empty());
+    }
+
+    #[test]
+    fn groups_are_created_once() {
+        let mut groups = Vec::new();
+        get_or_insert(&mut groups, "Books").paused = true;
+        assert!(get_or_insert(&mut groups, "Books").paused);
+        assert_eq!(groups.len(), 1);
+    }
+
+    #[test]
+    fn interval_follows_the_group() {
+        let groups = SharedGroups::default();
+        {
+            let mut groups = groups.lock().unwrap();
+            get_or_insert(&mut groups, "Fast").interval_minutes = 5;
+            get_or_insert(&mut groups, "Holiday").paused = true;
+            get_or_insert(&mut groups, "Default");
+        }
+        assert_eq!(row_interval(&groups, &row_in("Fast"), 60), Some(5));
+        assert_eq!(row_interval(&groups, &row_in("Holiday"), 60), None);
+        assert_eq!(row_interval(&groups, &row_in("Default"), 60), Some(60));
+        assert_eq!(row_interval(&groups, &row_in("Unknown"), 60), Some(60));
+    }
+}
@@ -0,0 +1,389 @@
+use chrono::Utc;
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use super::{HistoryEntry, ThisApp, ValueData, DEFAULT_CUSTOM_TIME_INTERVAL};
+
+/// Bump this and add a `migrate_vN` step whenever the persisted layout changes incompatibly.
+pub const CURRENT_SCHEMA_VERSION: u32 = 1;
+/// Stored next to the app state, so the version can be read before the state itself.
+pub const SCHEMA_VERSION_KEY: &str = "schema_version";
+
+pub struct LoadError {
+    pub message: String,
+    /// Whether the unreadable data was copied somewhere safe, so it may be overwritten.
+    pub backed_up: bool,
+}
+
+/// Layout before schema versions existed (v1.0.2 and earlier).
+#[derive(serde::Deserialize)]
+struct ThisAppV0 {
+    #[serde(default)]
+    table_data: Vec<ValueDataV0>,
+    #[serde(default)]
+    custom_time_interval: u64,
+}
+
+#[derive(serde::Deserialize)]
+struct ValueDataV0 {
+    id: String,
+    name: String,
+    link: String,
+    css_selector: String,
+    previous_value: String,
+    latest_value: String,
+    /// Local time formatted as "%b %d %H:%M:%S %Y".
+    last_updated: String,
+}
+
+/// Load the persisted app state, migrating it from older layouts.
+/// Returns `Ok(None)` when nothing has been saved yet.
+pub fn load(storage: &dyn eframe::Storage) -> Result<Option<ThisApp>, LoadError> {
+    load_with_backups(storage, backup_dir().as_deref())
+}
+
+/// Where copies of old or unreadable data are kept, `None` where there is no
+/// file system to keep them on.
+#[cfg(not(target_arch = "wasm32"))]
+fn backup_dir() -> Option<PathBuf> {
+    eframe::storage_dir(crate::APP_NAME)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn backup_dir() -> Option<PathBuf> {
+    None
+}
+
+fn load_with_backups(
+    storage: &dyn eframe::Storage,
+    backup_dir: Option<&Path>,
+) -> Result<Option<ThisApp>, LoadError> {
+    let Some(raw) = storage.get_string(eframe::APP_KEY) else {
+        return Ok(None);
+    };
+    let version = storage
+        .get_string(SCHEMA_VERSION_KEY)
+        .and_then(|version| version.trim().parse().ok())
+        .unwrap_or(0);
+
+    if version > CURRENT_SCHEMA_VERSION {
+        return Err(unreadable(
+            &raw,
+            version,
+            backup_dir,
+            format!(
+                "The saved data has schema version {} but this app only understands up to {}. \
+                 It was probably saved by a newer version of Web value tracker.",
+                version, CURRENT_SCHEMA_VERSION
+            ),
+        ));
+    }
+
+    if version < CURRENT_SCHEMA_VERSION {
+        // without a place for backups, e.g. on the web, refusing to migrate
+        // would keep the data from ever loading again
+        let Some(backup_dir) = backup_dir else {
+            println!(
+                "Migrating schema version {} data without a backup, there's nowhere to keep one",
+                version
+            );
+            return read(&raw, version, None);
+        };
+        match backup(backup_dir, &raw, version, "backup") {
+            Ok(path) => println!(
+                "Backed up schema version {} data to {:?} before migrating",
+                version, path
+            ),
+            Err(e) => {
+                return Err(LoadError {
+                    message: format!(
+                        "The saved data needs to be migrated from schema version {} \
+                         but it couldn't be backed up first: {}",
+                        version, e
+                    ),
+                    backed_up: false,
+                })
+            }
+        }
+    }
+
+    read(&raw, version, backup_dir)
+}
+
+fn read(raw: &str, version: u32, backup_dir: Option<&Path>) -> Result<Option<ThisApp>, LoadError> {
+    let result = match version {
+        0 => ron::from_str::<ThisAppV0>(raw).map(migrate_v0),
+        _ => ron::from_str::<ThisApp>(raw),
+    };
+    result.map(Some).map_err(|e| {
+        unreadable(
+            raw,
+            version,
+            backup_dir,
+            format!("The saved data couldn't be read: {}", e),
+        )
+    })
+}
+
+fn unreadable(raw: &str, version: u32, backup_dir: Option<&Path>, message: String) -> LoadError {
+    let Some(backup_dir) = backup_dir else {
+        return LoadError {
+            message: format!(
+                "{}\nThere's nowhere to copy it to, so it won't be overwritten.",
+                message
+            ),
+            backed_up: false,
+        };
+    };
+    match backup(backup_dir, raw, version, "unreadable") {
+        Ok(path) => LoadError {
+            message: format!("{}\nA copy was saved to {:?}.", message, path),
+            backed_up: true,
+        },
+        Err(e) => LoadError {
+            message: format!(
+                "{}\nIt couldn't be copied ({}), so it won't be overwritten.",
+                message, e
+            ),
+            backed_up: false,
+        },
+    }
+}
+
+fn backup(dir: &Path, raw: &str, version: u32, reason: &str) -> Result<PathBuf, String> {
+    std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    let path = dir.join(format!(
+        "app-v{}-{}-{}.ron",
+        version,
+        reason,
+        Utc::now().format("%Y%m%dT%H%M%SZ")
+    ));
+    std::fs::write(&path, raw).map_err(|e| e.to_string())?;
+    Ok(path)
+}
+
+/// v0 -> v1: timestamps become UTC, rows get lowest/highest values and a history.
+fn migrate_v0(old: ThisAppV0) -> ThisApp {
+    let table_data = old
+        .table_data
+        .into_iter()
+        .map(|row| {
+            let last_updated =
+                crate::timestamp::parse_legacy(&row.last_updated).unwrap_or_else(Utc::now);
+            let number = crate::parse_number(&row.latest_value);
+            ValueData {
+                id: row.id,
+                name: row.name,
+                link: row.link,
+                css_selector: row.css_selector,
+                mode: Default::default(),
+                fallback_selectors: Vec::new(),
+                matched_selector: None,
+                previous_value: row.previous_value,
+                latest_value: row.latest_value.clone(),
+                last_updated,
+                lowest_value: number,
+                highest_value: number,
+                on_change_command: String::new(),
+                group: String::new(),
+                tags: Vec::new(),
+                enabled: true,
+                paused_until: None,
+                fingerprint: None,
+                selector_suggestion: None,
+                transforms: Vec::new(),
+                browser: Default::default(),
+                session: Default::default(),
+                steps: Vec::new(),
+                script: String::new(),
+                fields: Vec::new(),
+                last_error: None,
+                failure_screenshot: None,
+                history: vec![HistoryEntry {
+                    value: row.latest_value,
+                    timestamp: last_updated,
+                    screenshot: None,
+                    fields: Vec::new(),
+                }],
+            }
+        })
+        .collect();
+
+    ThisApp {
+        table_data: Arc::new(Mutex::new(table_data)),
+        custom_time_interval: if old.custom_time_interval == 0 {
+            DEFAULT_CUSTOM_TIME_INTERVAL
+        } else {
+            old.custom_time_interval
+        },
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// State as saved by v1.0.2, before schema versions existed.
+    const V0_FIXTURE: &str = r#"(
+        table_data: [
+            (
+                id: "01J9Z3K2Q8V6T1X4M7N0P5R2S8",
+                name: "Book",
+                link: "https://www.amazon.com/dp/0000000000",
+                css_selector: ".aok-offscreen",
+                previous_value: "$12.99",
+                latest_value: "$10.49",
+                last_updated: "Oct 01 12:30:00 2024",
+            ),
+        ],
+        column_names: ["Name", "Link", "CSS Selector", "Previous Value", "Latest Value", "Last Updated"],
+        selected_rows: [false],
+        custom_time_interval: 0,
+    )"#;
+
+    #[derive(Default)]
+    struct MemoryStorage(HashMap<String, String>);
+
+    impl eframe::Storage for MemoryStorage {
+        fn get_string(&self, key: &str) -> Option<String> {
+            self.0.get(key).cloned()
+        }
+
+        fn set_string(&mut self, key: &str, value: String) {
+            self.0.insert(key.to_owned(), value);
+        }
+
+        fn flush(&mut self) {}
+    }
+
+    fn storage(raw: &str, version: Option<u32>) -> MemoryStorage {
+        let mut storage = MemoryStorage::default();
+        eframe::Storage::set_string(&mut storage, eframe::APP_KEY, raw.to_owned());
+        if let Some(version) = version {
+            eframe::Storage::set_string(&mut storage, SCHEMA_VERSION_KEY, version.to_string());
+        }
+        storage
+    }
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("wvt-migration-{}", ulid::Ulid::new()))
+    }
+
+    fn backups(dir: &Path) -> Vec<String> {
+        std::fs::read_dir(dir)
+            .map(|entries| {
+                entries
+                    .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn migrates_v0_after_backing_it_up() {
+        let dir = temp_dir();
+        let app = load_with_backups(&storage(V0_FIXTURE, None), Some(&dir))
+            .ok()
+            .unwrap()
+            .unwrap();
+
+        let rows = app.table_data.lock().unwrap();
+        assert_eq!(rows.len(), 1);
+        let row = &rows[0];
+        assert_eq!(row.id, "01J9Z3K2Q8V6T1X4M7N0P5R2S8");
+        assert_eq!(row.previous_value, "$12.99");
+        assert_eq!(row.latest_value, "$10.49");
+        assert_eq!(
+            row.last_updated,
+            crate::timestamp::parse_legacy("Oct 01 12:30:00 2024").unwrap()
+        );
+        assert_eq!(row.lowest_value, Some(10.49));
+        assert_eq!(row.history.len(), 1);
+        assert_eq!(app.custom_time_interval, DEFAULT_CUSTOM_TIME_INTERVAL);
+
+        let backups = backups(&dir);
+        assert_eq!(backups.len(), 1);
+        assert!(backups[0].starts_with("app-v0-backup-"));
+        assert_eq!(
+            std::fs::read_to_string(dir.join(&backups[0])).unwrap(),
+            V0_FIXTURE
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn migrates_without_anywhere_to_back_up() {
+        let app = load_with_backups(&storage(V0_FIXTURE, None), None)
+            .ok()
+            .unwrap()
+            .unwrap();
+        assert_eq!(app.table_data.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn loads_current_version_without_backup() {
+        let app = ThisApp::default();
+        app.table_data.lock().unwrap().push(ValueData::new(
+            "Book".to_owned(),
+            "https://example.com".to_owned(),
+            ".price".to_owned(),
+            "$10".to_owned(),
+        ));
+        let raw = ron::to_string(&app).unwrap();
+        let dir = temp_dir();
+        let loaded = load_with_backups(&storage(&raw, Some(CURRENT_SCHEMA_VERSION)), Some(&dir))
+            .ok()
+            .unwrap()
+            .unwrap();
+        assert_eq!(loaded.table_data.lock().unwrap()[0].latest_value, "$10");
+        assert!(backups(&dir).is_empty());
+    }
+
+    #[test]
+    fn nothing_saved_yet() {
+        let loaded = load_with_backups(&MemoryStorage::default(), None);
+        assert!(matches!(loaded, Ok(None)));
+    }
+
+    #[test]
+    fn unreadable_data_is_copied_before_it_can_be_overwritten() {
+        let dir = temp_dir();
+        let raw = "(table_data: [oops";
+        let Err(error) = load_with_backups(&storage(raw, Some(CURRENT_SCHEMA_VERSION)), Some(&dir))
+        else {
+            panic!("unreadable data loaded");
+        };
+        assert!(error.backed_up);
+        let backups = backups(&dir);
+        assert_eq!(backups.len(), 1);
+        assert!(backups[0].starts_with("app-v1-unreadable-"));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn unreadable_data_without_a_copy_is_kept() {
+        let raw = "(table_data: [oops";
+        let Err(error) = load_with_backups(&storage(raw, Some(CURRENT_SCHEMA_VERSION)), None)
+        else {
+            panic!("unreadable data loaded");
+        };
+        assert!(!error.backed_up);
+    }
+
+    #[test]
+    fn newer_version_is_refused() {
+        let dir = temp_dir();
+        let raw = ron::to_string(&ThisApp::default()).unwrap();
+        let result =
+            load_with_backups(&storage(&raw, Some(CURRENT_SCHEMA_VERSION + 1)), Some(&dir));
+        let Err(error) = result else {
+            panic!("data from a newer version loaded");
+        };
+        assert!(error.message.contains("newer version"));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
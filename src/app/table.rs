@@ -0,0 +1,676 @@
+use chrono::{DateTime, Duration, Utc};
+use egui::{Color32, RichText, ScrollArea, Sense, TextEdit, Ui};
+use egui_extras::{Column, TableBuilder};
+use std::{cell::RefCell, cmp::Ordering, collections::HashMap};
+
+use super::{group, RowMode, ThisApp, ValueData};
+
+const RECENT_CHANGE_HOURS: i64 = 24;
+
+#[derive(serde::Deserialize, serde::Serialize, PartialEq, Clone, Copy)]
+pub enum TableColumn {
+    Name,
+    Tags,
+    Link,
+    CssSelector,
+    PreviousValue,
+    LatestValue,
+    Change,
+    LastUpdated,
+    Status,
+}
+
+impl TableColumn {
+    fn as_str(&self) -> &'static str {
+        match self {
+            TableColumn::Name => "Name",
+            TableColumn::Tags => "Tags",
+            TableColumn::Link => "Link",
+            TableColumn::CssSelector => "CSS Selector",
+            TableColumn::PreviousValue => "Previous Value",
+            TableColumn::LatestValue => "Latest Value",
+            TableColumn::Change => "Change",
+            TableColumn::LastUpdated => "Last Updated",
+            TableColumn::Status => "Status",
+        }
+    }
+
+    fn get_columns() -> [TableColumn; 9] {
+        [
+            TableColumn::Name,
+            TableColumn::Tags,
+            TableColumn::Link,
+            TableColumn::CssSelector,
+            TableColumn::PreviousValue,
+            TableColumn::LatestValue,
+            TableColumn::Change,
+            TableColumn::LastUpdated,
+            TableColumn::Status,
+        ]
+    }
+
+    fn is_sortable(&self) -> bool {
+        matches!(
+            self,
+            TableColumn::Name
+                | TableColumn::LatestValue
+                | TableColumn::Change
+                | TableColumn::LastUpdated
+                | TableColumn::Status
+        )
+    }
+}
+
+/// Sorting, search and filters of the table.
+#[derive(serde::Deserialize, serde::Serialize, Default)]
+#[serde(default)]
+pub struct TableView {
+    sort_column: Option<TableColumn>,
+    sort_descending: bool,
+    search: String,
+    /// Only show rows with this tag, empty for all.
+    tag: String,
+    only_changed_recently: bool,
+    only_failing: bool,
+    /// Lowercase values of text rows by row id, with the update they're of,
+    /// so searching doesn't lowercase whole pages every frame.
+    #[serde(skip)]
+    lowercase_texts: RefCell<HashMap<String, (DateTime<Utc>, String)>>,
+}
+
+impl TableView {
+    fn matches(&self, row: &ValueData) -> bool {
+        let search = self.search.trim().to_lowercase();
+        if !search.is_empty()
+            && ![&row.name, &row.link, &row.group]
+                .into_iter()
+                .chain(&row.tags)
+                .any(|text| text.to_lowercase().contains(&search))
+            && !self.value_contains(row, &search)
+        {
+            return false;
+        }
+        if !self.tag.is_empty() && !row.tags.contains(&self.tag) {
+            return false;
+        }
+        if self.only_failing && !is_failing(row) {
+            return false;
+        }
+        if self.only_changed_recently {
+            let since = Utc::now() - Duration::hours(RECENT_CHANGE_HOURS);
+            // the first history entry is the value the row was added with
+            let changed = row.history.len() > 1
+                && row
+                    .history
+                    .last()
+                    .is_some_and(|entry| entry.timestamp >= since);
+            if !changed {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn value_contains(&self, row: &ValueData, search: &str) -> bool {
+        if !matches!(row.mode, RowMode::Text { .. }) {
+            return row.latest_value.to_lowercase().contains(search);
+        }
+        let mut lowercase_texts = self.lowercase_texts.borrow_mut();
+        let (updated, text) = lowercase_texts
+            .entry(row.id.clone())
+            .or_insert_with(|| (row.last_updated, row.latest_value.to_lowercase()));
+        if *updated != row.last_updated {
+            *updated = row.last_updated;
+            *text = row.latest_value.to_lowercase();
+        }
+        text.contains(search)
+    }
+
+    fn compare(&self, a: &ValueData, b: &ValueData) -> Ordering {
+        let ordering = match self.sort_column {
+            Some(TableColumn::Name) => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+            Some(TableColumn::LatestValue) => compare_values(&a.latest_value, &b.latest_value),
+            Some(TableColumn::Change) => change(a)
+                .unwrap_or(0.0)
+                .total_cmp(&change(b).unwrap_or(0.0)),
+            Some(TableColumn::LastUpdated) => a.last_updated.cmp(&b.last_updated),
+            Some(TableColumn::Status) => status(a).cmp(status(b)),
+            _ => Ordering::Equal,
+        };
+        if self.sort_descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    }
+
+    fn toggle_sort(&mut self, column: TableColumn) {
+        if self.sort_column == Some(column) {
+            if self.sort_descending {
+                // third click goes back to insertion order
+                self.sort_column = None;
+                self.sort_descending = false;
+            } else {
+                self.sort_descending = true;
+            }
+        } else {
+            self.sort_column = Some(column);
+            self.sort_descending = false;
+        }
+    }
+}
+
+fn is_failing(row: &ValueData) -> bool {
+    row.latest_value.is_empty()
+}
+
+fn status(row: &ValueData) -> &'static str {
+    if row.is_paused(Utc::now()) {
+        "Paused"
+    } else if is_failing(row) {
+        "Failing"
+    } else {
+        "OK"
+    }
+}
+
+/// Difference between the latest and previous value, when both are numbers.
+fn change(row: &ValueData) -> Option<f64> {
+    if !row.mode.is_numeric() {
+        return None;
+    }
+    Some(crate::parse_number(&row.latest_value)? - crate::parse_number(&row.previous_value)?)
+}
+
+fn change_text(row: &ValueData) -> String {
+    if let RowMode::Text { .. } = row.mode {
+        if row.latest_value == row.previous_value {
+            return String::new();
+        }
+        let (added, removed) = crate::diff::count_changes(&row.previous_value, &row.latest_value);
+        return format!("+{} -{} lines", added, removed);
+    }
+    match change(row) {
+        Some(change) if change != 0.0 => format!("{:+.2}", change),
+        Some(_) => String::new(),
+        None if row.latest_value != row.previous_value => "changed".to_owned(),
+        None => String::new(),
+    }
+}
+
+/// Compare as numbers when both values are numbers, otherwise as text.
+fn compare_values(a: &str, b: &str) -> Ordering {
+    match (crate::parse_number(a), crate::parse_number(b)) {
+        (Some(a), Some(b)) => a.total_cmp(&b),
+        _ => a.to_lowercase().cmp(&b.to_lowercase()),
+    }
+}
+
+impl ThisApp {
+    pub(super) fn table_filter_ui(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.label("🔍");
+            ui.add(
+                TextEdit::singleline(&mut self.table_view.search)
+                    .hint_text("search name, link, value or tag"),
+            );
+            let mut tags: Vec<String> = self
+                .table_data
+                .lock()
+                .unwrap()
+                .iter()
+                .flat_map(|row| row.tags.clone())
+                .collect();
+            tags.sort_by_key(|tag| tag.to_lowercase());
+            tags.dedup();
+            egui::ComboBox::from_id_salt("tag_filter")
+                .selected_text(if self.table_view.tag.is_empty() {
+                    "All tags"
+                } else {
+                    &self.table_view.tag
+                })
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.table_view.tag, String::new(), "All tags");
+                    for tag in tags {
+                        ui.selectable_value(&mut self.table_view.tag, tag.clone(), tag);
+                    }
+                });
+            ui.checkbox(
+                &mut self.table_view.only_changed_recently,
+                format!("Changed in last {} h", RECENT_CHANGE_HOURS),
+            );
+            ui.checkbox(&mut self.table_view.only_failing, "Failing");
+            if ui.button("Clear").clicked() {
+                self.table_view.search.clear();
+                self.table_view.tag.clear();
+                self.table_view.only_changed_recently = false;
+                self.table_view.only_failing = false;
+            }
+        });
+    }
+
+    /// Indices into `table_data` of the rows that pass the filters, in display order.
+    fn visible_rows(&self, table_data: &[ValueData]) -> Vec<usize> {
+        let mut indices: Vec<usize> = (0..table_data.len())
+            .filter(|&index| self.table_view.matches(&table_data[index]))
+            .collect();
+        if self.table_view.sort_column.is_some() {
+            indices.sort_by(|&a, &b| self.table_view.compare(&table_data[a], &table_data[b]));
+        }
+        indices
+    }
+
+    pub(super) fn table_ui(&mut self, ui: &mut Ui) {
+        let table_action = self.grouped_table_ui(ui);
+        match table_action {
+            Some(TableAction::Fetch(ids)) => {
+                let rows: Vec<ValueData> = self
+                    .table_data
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .filter(|row| ids.contains(&row.id))
+                    .cloned()
+                    .collect();
+                self.fetch_rows(&rows);
+            }
+            Some(TableAction::ShowScreenshot(path)) => {
+                self.runtime_state.viewed_screenshot = Some(path);
+            }
+            Some(TableAction::ShowDiff(id)) => {
+                self.runtime_state.viewed_diff = Some(id);
+            }
+            Some(TableAction::Delete(ids)) => {
+                self.runtime_state.selected_rows = ids.into_iter().collect();
+                self.delete_selected_rows();
+            }
+            Some(TableAction::AcceptSuggestion(id)) => {
+                let mut table_data = self.table_data.lock().unwrap();
+                if let Some(row) = table_data.iter_mut().find(|row| row.id == id) {
+                    if let Some(suggestion) = row.selector_suggestion.take() {
+                        row.css_selector = suggestion.selector;
+                        crate::apply_new_value(
+                            row,
+                            crate::FetchResult {
+                                value: suggestion.value,
+                                matched_selector: Some(0),
+                                ..Default::default()
+                            },
+                        );
+                    }
+                }
+                crate::update_computed_rows(&mut table_data);
+            }
+            Some(TableAction::DismissSuggestion(id)) => {
+                let mut table_data = self.table_data.lock().unwrap();
+                if let Some(row) = table_data.iter_mut().find(|row| row.id == id) {
+                    row.selector_suggestion = None;
+                }
+            }
+            None => {}
+        }
+    }
+
+    fn grouped_table_ui(&mut self, ui: &mut Ui) -> Option<TableAction> {
+        let groups = self.groups.clone();
+        let mut groups = groups.lock().unwrap();
+        let table_data = self.table_data.clone();
+        let table_data = table_data.lock().unwrap();
+        let visible_rows = self.visible_rows(&table_data);
+
+        // only rows in the current view can stay selected
+        self.runtime_state.selected_rows.retain(|id| {
+            visible_rows
+                .iter()
+                .any(|&index| table_data[index].id == *id)
+        });
+
+        // named groups alphabetically, ungrouped rows last
+        groups.retain(|group| table_data.iter().any(|row| row.group == group.name));
+        let mut group_names: Vec<&str> = visible_rows
+            .iter()
+            .map(|&index| table_data[index].group.as_str())
+            .collect();
+        group_names.sort_by_key(|name| (name.is_empty(), name.to_lowercase()));
+        group_names.dedup();
+        let show_group_headers = group_names.iter().any(|name| !name.is_empty());
+        let fetching = self.runtime_state.fetching_latest_values;
+        let mut table_action = None;
+
+        let columns = TableColumn::get_columns();
+        ScrollArea::horizontal().show(ui, |ui| {
+            TableBuilder::new(ui)
+                .striped(true)
+                .resizable(true)
+                .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+                .column(Column::auto()) // Checkbox column
+                .column(Column::auto().clip(true))
+                .column(Column::auto().clip(true))
+                .columns(Column::auto().clip(true), columns.len() - 2)
+                .header(20.0, |mut header| {
+                    header.col(|ui| {
+                        let selected_rows = &mut self.runtime_state.selected_rows;
+                        let mut all_selected =
+                            !visible_rows.is_empty() && selected_rows.len() == visible_rows.len();
+                        if ui
+                            .checkbox(&mut all_selected, RichText::new("Select").strong())
+                            .changed()
+                        {
+                            selected_rows.clear();
+                            if all_selected {
+                                selected_rows.extend(
+                                    visible_rows
+                                        .iter()
+                                        .map(|&index| table_data[index].id.clone()),
+                                );
+                            }
+                        }
+                    });
+                    for column in columns {
+                        header.col(|ui| {
+                            if !column.is_sortable() {
+                                ui.strong(column.as_str());
+                                return;
+                            }
+                            let arrow = match self.table_view.sort_column {
+                                Some(sorted) if sorted == column => {
+                                    if self.table_view.sort_descending {
+                                        " ⏷"
+                                    } else {
+                                        " ⏶"
+                                    }
+                                }
+                                _ => "",
+                            };
+                            let label = egui::Label::new(
+                                RichText::new(format!("{}{}", column.as_str(), arrow)).strong(),
+                            )
+                            .sense(Sense::click());
+                            if ui.add(label).on_hover_text("Sort").clicked() {
+                                self.table_view.toggle_sort(column);
+                            }
+                        });
+                    }
+                })
+                .body(|mut body| {
+                    for name in &group_names {
+                        let group_rows: Vec<usize> = visible_rows
+                            .iter()
+                            .copied()
+                            .filter(|&index| table_data[index].group == *name)
+                            .collect();
+                        if show_group_headers {
+                            let group = group::get_or_insert(&mut groups, name);
+                            body.row(25.0, |mut row| {
+                                row.col(|ui| {
+                                    let icon = if group.collapsed { "⏵" } else { "⏷" };
+                                    if ui.small_button(icon).clicked() {
+                                        group.collapsed = !group.collapsed;
+                                    }
+                                });
+                                row.col(|ui| {
+                                    ui.strong(format!(
+                                        "{} ({})",
+                                        group.display_name(),
+                                        group_rows.len()
+                                    ));
+                                    if group.paused {
+                                        ui.label("⏸").on_hover_text("Paused");
+                                    }
+                                });
+                                row.col(|ui| {
+                                    ui.menu_button("⚙ Group actions", |ui| {
+                                        let ids = || {
+                                            group_rows
+                                                .iter()
+                                                .map(|&index| table_data[index].id.clone())
+                                                .collect()
+                                        };
+                                        if ui
+                                            .add_enabled(!fetching, egui::Button::new("🔄 Fetch"))
+                                            .clicked()
+                                        {
+                                            table_action = Some(TableAction::Fetch(ids()));
+                                            ui.close_menu();
+                                        }
+                                        ui.checkbox(&mut group.paused, "⏸ Paused");
+                                        if ui.button("🗑 Delete rows").clicked() {
+                                            table_action = Some(TableAction::Delete(ids()));
+                                            ui.close_menu();
+                                        }
+                                        ui.horizontal(|ui| {
+                                            ui.label("Check every");
+                                            ui.add(egui::DragValue::new(
+                                                &mut group.interval_minutes,
+                                            ));
+                                            ui.label("minutes (0 = global)");
+                                        });
+                                        ui.colored_label(
+                                            Color32::RED,
+                                            "* Restart the app to apply interval changes.",
+                                        );
+                                    });
+                                });
+                                for _ in 2..columns.len() {
+                                    row.col(|_| {});
+                                }
+                            });
+                            if group.collapsed {
+                                continue;
+                            }
+                        }
+                        for &row_index in &group_rows {
+                            let row_data = &table_data[row_index];
+                            let mut row_is_selected =
+                                self.runtime_state.selected_rows.contains(&row_data.id);
+                            body.row(25.0, |mut row| {
+                                row.col(|ui| {
+                                    if ui.checkbox(&mut row_is_selected, "").changed() {
+                                        if row_is_selected {
+                                            self.runtime_state
+                                                .selected_rows
+                                                .insert(row_data.id.clone());
+                                        } else {
+                                            self.runtime_state.selected_rows.remove(&row_data.id);
+                                        }
+                                        ui.ctx().request_repaint(); // Ensure UI updates immediately
+                                    }
+                                });
+                                for column in columns {
+                                    let value = match column {
+                                        TableColumn::Name => row_data.name.clone(),
+                                        TableColumn::Tags => row_data.tags.join(", "),
+                                        TableColumn::Link => row_data.link.clone(),
+                                        TableColumn::CssSelector => match &row_data.mode {
+                                            RowMode::Computed { formula } => {
+                                                format!("= {}", formula)
+                                            }
+                                            _ => row_data.css_selector.clone(),
+                                        },
+                                        TableColumn::PreviousValue => {
+                                            row_data.display_value(&row_data.previous_value)
+                                        }
+                                        TableColumn::LatestValue => {
+                                            row_data.display_value(&row_data.latest_value)
+                                        }
+                                        TableColumn::Change => change_text(row_data),
+                                        TableColumn::LastUpdated => self
+                                            .time_display_settings
+                                            .format(&row_data.last_updated),
+                                        TableColumn::Status => status(row_data).to_owned(),
+                                    };
+                                    row.col(|ui| {
+                                        let text_color = if column == TableColumn::Status
+                                            && is_failing(row_data)
+                                        {
+                                            Color32::RED
+                                        } else if column == TableColumn::LatestValue
+                                            && matches!(row_data.mode, RowMode::Availability(_))
+                                            && row_data.latest_value
+                                                == crate::availability::IN_STOCK
+                                        {
+                                            Color32::GREEN
+                                        } else if row_is_selected {
+                                            ui.ctx().style().visuals.strong_text_color()
+                                        } else {
+                                            ui.ctx().style().visuals.text_color()
+                                        };
+                                        let label = ui.colored_label(text_color, value);
+                                        if column == TableColumn::CssSelector {
+                                            if let Some(index) =
+                                                row_data.matched_selector.filter(|&index| index > 0)
+                                            {
+                                                ui.colored_label(Color32::YELLOW, "⚠ fallback")
+                                                    .on_hover_text(format!(
+                                                        "The selector found nothing, the value \
+                                                         was read with fallback {:?}.",
+                                                        row_data
+                                                            .selectors()
+                                                            .get(index)
+                                                            .copied()
+                                                            .unwrap_or_default()
+                                                    ));
+                                            }
+                                            if let Some(suggestion) = &row_data.selector_suggestion
+                                            {
+                                                ui.menu_button("💡", |ui| {
+                                                    ui.label(format!(
+                                                        "The selector stopped matching. \
+                                                         {:?} looks like the same element \
+                                                         and reads {:?}.",
+                                                        suggestion.selector, suggestion.value
+                                                    ));
+                                                    if ui.button("Use it").clicked() {
+                                                        table_action =
+                                                            Some(TableAction::AcceptSuggestion(
+                                                                row_data.id.clone(),
+                                                            ));
+                                                        ui.close_menu();
+                                                    }
+                                                    if ui.button("Dismiss").clicked() {
+                                                        table_action =
+                                                            Some(TableAction::DismissSuggestion(
+                                                                row_data.id.clone(),
+                                                            ));
+                                                        ui.close_menu();
+                                                    }
+                                                })
+                                                .response
+                                                .on_hover_text("Suggested selector");
+                                            }
+                                        }
+                                        let screenshot = match column {
+                                            TableColumn::LatestValue => row_data
+                                                .history
+                                                .last()
+                                                .filter(|entry| {
+                                                    entry.value == row_data.latest_value
+                                                })
+                                                .and_then(|entry| entry.screenshot.as_ref()),
+                                            TableColumn::Status => {
+                                                row_data.failure_screenshot.as_ref()
+                                            }
+                                            _ => None,
+                                        };
+                                        if column == TableColumn::Change
+                                            && matches!(row_data.mode, RowMode::Text { .. })
+                                            && row_data.latest_value != row_data.previous_value
+                                            && ui
+                                                .small_button("📄")
+                                                .on_hover_text("Show what changed")
+                                                .clicked()
+                                        {
+                                            table_action =
+                                                Some(TableAction::ShowDiff(row_data.id.clone()));
+                                        }
+                                        if column == TableColumn::LatestValue
+                                            && !row_data.fields.is_empty()
+                                        {
+                                            ui.weak(format!("+{}", row_data.fields.len()))
+                                                .on_hover_text(crate::fields::summary(
+                                                    &row_data.fields,
+                                                ));
+                                        }
+                                        if let Some(path) = screenshot {
+                                            if ui
+                                                .small_button("📷")
+                                                .on_hover_text("What the page looked like")
+                                                .clicked()
+                                            {
+                                                table_action =
+                                                    Some(TableAction::ShowScreenshot(path.clone()));
+                                            }
+                                        }
+                                        if column == TableColumn::LastUpdated {
+                                            label.on_hover_text(
+                                                self.time_display_settings
+                                                    .format_absolute(&row_data.last_updated),
+                                            );
+                                        } else if column == TableColumn::Status
+                                            && row_data.is_paused(Utc::now())
+                                        {
+                                            if let Some(until) = &row_data.paused_until {
+                                                label.on_hover_text(format!(
+                                                    "Until {}",
+                                                    self.time_display_settings
+                                                        .format_absolute(until)
+                                                ));
+                                            }
+                                        } else if column == TableColumn::Status {
+                                            if let Some(error) = &row_data.last_error {
+                                                label.on_hover_text(error);
+                                            }
+                                        }
+                                    });
+                                }
+                            });
+                        }
+                    }
+                });
+        });
+        table_action
+    }
+}
+
+/// Actions that need the rows unlocked, applied after the table is drawn.
+enum TableAction {
+    Fetch(Vec<String>),
+    Delete(Vec<String>),
+    AcceptSuggestion(String),
+    DismissSuggestion(String),
+    /// Path of the screenshot to show.
+    ShowScreenshot(String),
+    ShowDiff(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn search_sees_updated_text() {
+        let mut row = ValueData::new(
+            "Notes".to_owned(),
+            "https://example.com".to_owned(),
+            "main".to_owned(),
+            "Sold OUT".to_owned(),
+        );
+        row.mode = RowMode::Text { html: false };
+        let mut view = TableView {
+            search: "sold out".to_owned(),
+            ..Default::default()
+        };
+        assert!(view.matches(&row));
+
+        crate::apply_new_value(
+            &mut row,
+            crate::FetchResult {
+                value: "In stock".to_owned(),
+                ..Default::default()
+            },
+        );
+        assert!(!view.matches(&row));
+        view.search = "IN STOCK".to_owned();
+        assert!(view.matches(&row));
+    }
+}
@@ -0,0 +1,216 @@
+use egui::{TextEdit, Ui};
+use headless_chrome::Tab;
+use std::error::Error;
+
+pub const IN_STOCK: &str = "In stock";
+pub const OUT_OF_STOCK: &str = "Out of stock";
+
+#[derive(serde::Deserialize, serde::Serialize, schemars::JsonSchema, Clone, Copy, PartialEq)]
+pub enum AvailabilityCheck {
+    /// An element matches the selector, e.g. an "Add to cart" button.
+    Exists,
+    /// Nothing matches the selector.
+    Missing,
+    /// The selector's text, or the page's without one, contains the text.
+    Contains,
+    /// The text doesn't appear, e.g. "Currently unavailable".
+    NotContains,
+}
+
+impl AvailabilityCheck {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AvailabilityCheck::Exists => "Element exists",
+            AvailabilityCheck::Missing => "Element missing",
+            AvailabilityCheck::Contains => "Text present",
+            AvailabilityCheck::NotContains => "Text absent",
+        }
+    }
+
+    pub fn get_checks() -> [AvailabilityCheck; 4] {
+        [
+            AvailabilityCheck::Exists,
+            AvailabilityCheck::Missing,
+            AvailabilityCheck::Contains,
+            AvailabilityCheck::NotContains,
+        ]
+    }
+
+    fn uses_text(&self) -> bool {
+        matches!(
+            self,
+            AvailabilityCheck::Contains | AvailabilityCheck::NotContains
+        )
+    }
+}
+
+/// One condition an item must meet to count as available.
+#[derive(serde::Deserialize, serde::Serialize, schemars::JsonSchema, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct AvailabilityRule {
+    pub check: AvailabilityCheck,
+    /// Optional for the text checks, which then look at the whole page.
+    pub selector: String,
+    /// Compared ignoring case.
+    #[serde(default)]
+    pub text: String,
+}
+
+/// Why `rules` can't be checked, if they can't.
+pub fn validate_rules(rules: &[AvailabilityRule]) -> Result<(), String> {
+    if rules.is_empty() {
+        return Err("no availability rules".to_owned());
+    }
+    match rules
+        .iter()
+        .find(|rule| !rule.check.uses_text() && rule.selector.trim().is_empty())
+    {
+        Some(rule) => Err(format!("{:?} needs a css selector", rule.check.as_str())),
+        None => Ok(()),
+    }
+}
+
+/// Whether the page `tab` is on meets all `rules`.
+pub fn is_available(tab: &Tab, rules: &[AvailabilityRule]) -> Result<bool, Box<dyn Error>> {
+    validate_rules(rules)?;
+    let js = format!(
+        r##"
+((rules) => JSON.stringify(rules.map((rule) => {{
+    const element = rule.selector ? document.querySelector(rule.selector) : document.body;
+    const text = (element?.innerText ?? '').toLowerCase();
+    switch (rule.check) {{
+        case 'Exists': return element !== null;
+        case 'Missing': return element === null;
+        case 'Contains': return text.includes(rule.text.toLowerCase());
+        case 'NotContains': return !text.includes(rule.text.toLowerCase());
+    }}
+    return false;
+}})))({})
+"##,
+        serde_json::to_string(rules)?
+    );
+    let result = tab.evaluate(&js, false)?;
+    let json = result
+        .value
+        .as_ref()
+        .and_then(|value| value.as_str())
+        .ok_or("couldn't check the page")?;
+    let passed: Vec<bool> = serde_json::from_str(json)?;
+    for (rule, passed) in rules.iter().zip(&passed) {
+        if !passed {
+            println!(
+                "Availability rule {:?} {:?} {:?} not met",
+                rule.check.as_str(),
+                rule.selector,
+                rule.text
+            );
+        }
+    }
+    Ok(passed.iter().all(|&passed| passed))
+}
+
+pub fn status_text(available: bool) -> &'static str {
+    if available {
+        IN_STOCK
+    } else {
+        OUT_OF_STOCK
+    }
+}
+
+/// Editable list of rules.
+pub fn rules_ui(ui: &mut Ui, rules: &mut Vec<AvailabilityRule>) {
+    let mut removed = None;
+    for (index, rule) in rules.iter_mut().enumerate() {
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_salt(("availability_rule", index))
+                .selected_text(rule.check.as_str())
+                .show_ui(ui, |ui| {
+                    for check in AvailabilityCheck::get_checks() {
+                        ui.selectable_value(&mut rule.check, check, check.as_str());
+                    }
+                });
+            let hint = if rule.check.uses_text() {
+                "css selector, empty for the page"
+            } else {
+                "css selector"
+            };
+            ui.add(
+                TextEdit::singleline(&mut rule.selector)
+                    .hint_text(hint)
+                    .desired_width(160.0),
+            );
+            if rule.check.uses_text() {
+                ui.add(
+                    TextEdit::singleline(&mut rule.text)
+                        .hint_text("e.g. currently unavailable")
+                        .desired_width(140.0),
+                );
+            }
+            if ui.small_button("🗑").clicked() {
+                removed = Some(index);
+            }
+        });
+    }
+    // an empty list only needs its first rule, which the button below adds
+    if !rules.is_empty() {
+        if let Err(error) = validate_rules(rules) {
+            ui.colored_label(egui::Color32::RED, error);
+        }
+    }
+    if let Some(index) = removed {
+        rules.remove(index);
+    }
+    if ui.small_button("➕ Add rule").clicked() {
+        rules.push(AvailabilityRule {
+            check: AvailabilityCheck::Exists,
+            selector: String::new(),
+            text: String::new(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(check: AvailabilityCheck, selector: &str) -> AvailabilityRule {
+        AvailabilityRule {
+            check,
+            selector: selector.to_owned(),
+            text: "sold out".to_owned(),
+        }
+    }
+
+    #[test]
+    fn rules_need_selectors_for_element_checks() {
+        assert!(validate_rules(&[]).is_err());
+        assert!(validate_rules(&[rule(AvailabilityCheck::NotContains, "")]).is_ok());
+        assert!(validate_rules(&[
+            rule(AvailabilityCheck::Exists, "#add-to-cart"),
+            rule(AvailabilityCheck::Contains, ""),
+        ])
+        .is_ok());
+        let error = validate_rules(&[
+            rule(AvailabilityCheck::Contains, ""),
+            rule(AvailabilityCheck::Missing, " "),
+        ])
+        .unwrap_err();
+        assert!(error.contains("Element missing"), "{}", error);
+    }
+
+    #[test]
+    fn checks_are_sent_under_the_names_the_page_script_expects() {
+        for check in AvailabilityCheck::get_checks() {
+            let name = serde_json::to_value(check).unwrap();
+            let expected = match check {
+                AvailabilityCheck::Exists => "Exists",
+                AvailabilityCheck::Missing => "Missing",
+                AvailabilityCheck::Contains => "Contains",
+                AvailabilityCheck::NotContains => "NotContains",
+            };
+            assert_eq!(name, expected);
+        }
+        assert_eq!(status_text(true), IN_STOCK);
+        assert_eq!(status_text(false), OUT_OF_STOCK);
+    }
+}
@@ -0,0 +1,358 @@
+use headless_chrome::{Browser, LaunchOptions, Tab};
+use regex::Regex;
+use std::{
+    collections::HashSet,
+    error::Error,
+    ffi::{OsStr, OsString},
+    path::PathBuf,
+    sync::{Arc, Condvar, Mutex, OnceLock, RwLock},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// A visible browser waits on the user, so it isn't closed for being idle this soon.
+const VISIBLE_BROWSER_IDLE_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+/// headless_chrome's own timeout for navigations and element waits.
+const DEFAULT_TIMEOUT_SECS: u64 = 20;
+/// The network counts as idle once no request has finished for this long.
+const NETWORK_IDLE_QUIET_PERIOD: Duration = Duration::from_millis(500);
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How Chrome is started for every fetch.
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct BrowserSettings {
+    /// Empty to let headless_chrome find or download Chrome.
+    pub chrome_path: String,
+    /// Empty for Chrome's own user agent.
+    pub user_agent: String,
+    /// 0 for Chrome's default size.
+    pub window_width: u32,
+    pub window_height: u32,
+    /// e.g. "http://proxy:3128" or "socks5://localhost:1080", empty for none.
+    pub proxy_server: String,
+    /// Extra command line switches, one per line.
+    pub extra_args: String,
+    /// Sent as Accept-Language and used as the browser language, e.g. "de-DE".
+    pub locale: String,
+    /// Turning the sandbox off is needed to run as root, e.g. in containers.
+    pub sandbox: bool,
+    /// Chrome profile directory kept between fetches, so logins and site data
+    /// persist. Empty for a fresh temporary profile every time. Chrome only
+    /// runs once per profile, so rows sharing one are fetched one at a time.
+    pub profile_dir: String,
+    /// How long a page may take to load.
+    pub navigation_timeout_secs: u64,
+    /// How long to wait for selectors, including those of steps.
+    pub element_timeout_secs: u64,
+    /// After loading, also wait until the page stops making requests.
+    pub wait_for_network_idle: bool,
+    /// What the read text must look like before it's accepted.
+    pub wait_for_text: TextCondition,
+}
+
+impl Default for BrowserSettings {
+    fn default() -> Self {
+        Self {
+            chrome_path: String::new(),
+            user_agent: String::new(),
+            window_width: 0,
+            window_height: 0,
+            proxy_server: String::new(),
+            extra_args: String::new(),
+            locale: String::new(),
+            sandbox: true,
+            profile_dir: String::new(),
+            navigation_timeout_secs: DEFAULT_TIMEOUT_SECS,
+            element_timeout_secs: DEFAULT_TIMEOUT_SECS,
+            wait_for_network_idle: false,
+            wait_for_text: TextCondition::default(),
+        }
+    }
+}
+
+/// Per-row replacements for the global settings, empty fields keep the global value.
+#[derive(serde::Deserialize, serde::Serialize, schemars::JsonSchema, Clone, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct BrowserOverrides {
+    pub user_agent: String,
+    pub proxy_server: String,
+    pub locale: String,
+    /// Added after the global extra args, one per line.
+    pub extra_args: String,
+    pub profile_dir: String,
+    /// 0 for the global timeout.
+    pub navigation_timeout_secs: u64,
+    /// 0 for the global timeout.
+    pub element_timeout_secs: u64,
+    pub wait_for_network_idle: Option<bool>,
+    pub wait_for_text: Option<TextCondition>,
+}
+
+impl BrowserOverrides {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// When text read from the page is good enough to use. Sites often render a
+/// placeholder first and fill the real value in later.
+#[derive(serde::Deserialize, serde::Serialize, schemars::JsonSchema, Clone, Default, PartialEq)]
+pub enum TextCondition {
+    /// Take whatever text the element has once it exists.
+    #[default]
+    Present,
+    NonEmpty,
+    Matches(String),
+}
+
+impl TextCondition {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TextCondition::Present => "Element exists",
+            TextCondition::NonEmpty => "Text isn't empty",
+            TextCondition::Matches(_) => "Text matches",
+        }
+    }
+
+    pub fn get_conditions() -> [TextCondition; 3] {
+        [
+            TextCondition::Present,
+            TextCondition::NonEmpty,
+            TextCondition::Matches(String::new()),
+        ]
+    }
+
+    /// The condition ready to check text against, with its pattern compiled
+    /// once for all the polls of a read.
+    pub fn compile(&self) -> Result<TextCheck, String> {
+        Ok(match self {
+            TextCondition::Present => TextCheck::Present,
+            TextCondition::NonEmpty => TextCheck::NonEmpty,
+            TextCondition::Matches(pattern) => TextCheck::Matches(
+                Regex::new(pattern)
+                    .map_err(|e| format!("invalid wait pattern {:?}: {}", pattern, e))?,
+            ),
+        })
+    }
+}
+
+/// A compiled `TextCondition`.
+pub enum TextCheck {
+    Present,
+    NonEmpty,
+    Matches(Regex),
+}
+
+impl TextCheck {
+    /// Whether the text read so far is final, rather than something to wait out.
+    pub fn is_met(&self, text: &str) -> bool {
+        match self {
+            TextCheck::Present => true,
+            TextCheck::NonEmpty => !text.trim().is_empty(),
+            TextCheck::Matches(regex) => regex.is_match(text),
+        }
+    }
+}
+
+fn global_settings() -> &'static RwLock<BrowserSettings> {
+    static SETTINGS: OnceLock<RwLock<BrowserSettings>> = OnceLock::new();
+    SETTINGS.get_or_init(Default::default)
+}
+
+/// Use `settings` for browsers launched from now on.
+pub fn set_global_settings(settings: &BrowserSettings) {
+    *global_settings().write().unwrap() = settings.clone();
+}
+
+/// The global settings with a row's overrides applied.
+pub fn effective_settings(overrides: &BrowserOverrides) -> BrowserSettings {
+    let mut settings = global_settings().read().unwrap().clone();
+    if !overrides.user_agent.is_empty() {
+        settings.user_agent = overrides.user_agent.clone();
+    }
+    if !overrides.proxy_server.is_empty() {
+        settings.proxy_server = overrides.proxy_server.clone();
+    }
+    if !overrides.locale.is_empty() {
+        settings.locale = overrides.locale.clone();
+    }
+    if !overrides.extra_args.is_empty() {
+        settings.extra_args = format!("{}\n{}", settings.extra_args, overrides.extra_args);
+    }
+    if !overrides.profile_dir.is_empty() {
+        settings.profile_dir = overrides.profile_dir.clone();
+    }
+    if overrides.navigation_timeout_secs > 0 {
+        settings.navigation_timeout_secs = overrides.navigation_timeout_secs;
+    }
+    if overrides.element_timeout_secs > 0 {
+        settings.element_timeout_secs = overrides.element_timeout_secs;
+    }
+    if let Some(wait_for_network_idle) = overrides.wait_for_network_idle {
+        settings.wait_for_network_idle = wait_for_network_idle;
+    }
+    if let Some(wait_for_text) = &overrides.wait_for_text {
+        settings.wait_for_text = wait_for_text.clone();
+    }
+    settings
+}
+
+/// Profile directories a launched browser is using right now.
+fn profiles_in_use() -> &'static (Mutex<HashSet<PathBuf>>, Condvar) {
+    static IN_USE: OnceLock<(Mutex<HashSet<PathBuf>>, Condvar)> = OnceLock::new();
+    IN_USE.get_or_init(Default::default)
+}
+
+/// Claims a profile directory until dropped, since a second Chrome opening
+/// the same profile fails on its single-instance lock.
+struct ProfileLock(PathBuf);
+
+impl ProfileLock {
+    /// Wait until no other browser uses `profile_dir`.
+    fn acquire(profile_dir: PathBuf) -> Self {
+        let (in_use, released) = profiles_in_use();
+        let mut in_use = in_use.lock().unwrap();
+        while in_use.contains(&profile_dir) {
+            in_use = released.wait(in_use).unwrap();
+        }
+        in_use.insert(profile_dir.clone());
+        ProfileLock(profile_dir)
+    }
+}
+
+impl Drop for ProfileLock {
+    fn drop(&mut self) {
+        let (in_use, released) = profiles_in_use();
+        in_use.lock().unwrap().remove(&self.0);
+        released.notify_all();
+    }
+}
+
+/// A running Chrome, closed when dropped. Holds on to its profile until then.
+pub struct LaunchedBrowser {
+    _browser: Browser,
+    // dropped after the browser, so the next one finds the profile closed
+    _profile: Option<ProfileLock>,
+}
+
+/// Start Chrome with `settings` and open a tab that sends the configured user agent and language.
+pub fn launch(
+    settings: &BrowserSettings,
+    headless: bool,
+) -> Result<(LaunchedBrowser, Arc<Tab>), Box<dyn Error>> {
+    let profile_dir =
+        (!settings.profile_dir.is_empty()).then(|| PathBuf::from(&settings.profile_dir));
+    let profile = profile_dir.clone().map(ProfileLock::acquire);
+    let mut args: Vec<OsString> = settings
+        .extra_args
+        .lines()
+        .map(str::trim)
+        .filter(|arg| !arg.is_empty())
+        .map(OsString::from)
+        .collect();
+    if !settings.locale.is_empty() {
+        args.push(format!("--lang={}", settings.locale).into());
+    }
+
+    let browser = Browser::new(LaunchOptions {
+        headless,
+        sandbox: settings.sandbox,
+        path: (!settings.chrome_path.is_empty()).then(|| PathBuf::from(&settings.chrome_path)),
+        window_size: (settings.window_width > 0 && settings.window_height > 0)
+            .then_some((settings.window_width, settings.window_height)),
+        proxy_server: (!settings.proxy_server.is_empty()).then_some(settings.proxy_server.as_str()),
+        args: args
+            .iter()
+            .map(OsString::as_os_str)
+            .collect::<Vec<&OsStr>>(),
+        user_data_dir: profile_dir,
+        idle_browser_timeout: if headless {
+            LaunchOptions::default().idle_browser_timeout
+        } else {
+            VISIBLE_BROWSER_IDLE_TIMEOUT
+        },
+        ..Default::default()
+    })?;
+
+    let tab = browser.new_tab()?;
+    if !settings.user_agent.is_empty() || !settings.locale.is_empty() {
+        let user_agent = if settings.user_agent.is_empty() {
+            browser.get_version()?.user_agent
+        } else {
+            settings.user_agent.clone()
+        };
+        let accept_language = (!settings.locale.is_empty()).then_some(settings.locale.as_str());
+        tab.set_user_agent(&user_agent, accept_language, None)?;
+    }
+    Ok((
+        LaunchedBrowser {
+            _browser: browser,
+            _profile: profile,
+        },
+        tab,
+    ))
+}
+
+/// Wait for the page `tab` is navigating to as `settings` say, then use the
+/// element timeout for everything that follows.
+pub fn wait_for_page(tab: &Tab, settings: &BrowserSettings) -> Result<(), Box<dyn Error>> {
+    let navigation_timeout = Duration::from_secs(settings.navigation_timeout_secs.max(1));
+    tab.set_default_timeout(navigation_timeout);
+    tab.wait_until_navigated()?;
+    if settings.wait_for_network_idle {
+        wait_for_network_idle(tab, navigation_timeout)?;
+    }
+    tab.set_default_timeout(Duration::from_secs(settings.element_timeout_secs.max(1)));
+    Ok(())
+}
+
+/// Wait until no request has finished for a moment, judged by the page's
+/// resource timing entries. Gives up quietly after `timeout`, since pages
+/// that poll forever are still worth reading.
+fn wait_for_network_idle(tab: &Tab, timeout: Duration) -> Result<(), Box<dyn Error>> {
+    let started = Instant::now();
+    let mut finished_requests = None;
+    let mut quiet_since = Instant::now();
+    while started.elapsed() < timeout {
+        let count = tab
+            .evaluate("performance.getEntriesByType('resource').length", false)?
+            .value
+            .and_then(|value| value.as_u64());
+        if count != finished_requests {
+            finished_requests = count;
+            quiet_since = Instant::now();
+        } else if quiet_since.elapsed() >= NETWORK_IDLE_QUIET_PERIOD {
+            return Ok(());
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+    println!("Network didn't become idle within {:?}", timeout);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_conditions_are_checked() {
+        let present = TextCondition::Present.compile().unwrap();
+        assert!(present.is_met(""));
+        let non_empty = TextCondition::NonEmpty.compile().unwrap();
+        assert!(!non_empty.is_met(" \n"));
+        assert!(non_empty.is_met("Loading"));
+        let digits = TextCondition::Matches(r"\d".to_owned()).compile().unwrap();
+        assert!(!digits.is_met("Loading"));
+        assert!(digits.is_met("$12"));
+    }
+
+    #[test]
+    fn invalid_patterns_are_configuration_errors() {
+        let error = TextCondition::Matches("(\\d".to_owned())
+            .compile()
+            .err()
+            .unwrap();
+        assert!(error.starts_with("invalid wait pattern"), "{}", error);
+    }
+}
@@ -0,0 +1,227 @@
+use std::collections::HashMap;
+
+/// Above this many line pairs the exact diff is too slow to run on every
+/// check, and changed lines are matched by content alone.
+const MAX_EXACT_DIFF_CELLS: usize = 4_000_000;
+/// Unchanged lines shown around each change.
+pub const CONTEXT_LINES: usize = 2;
+
+#[derive(Clone, PartialEq)]
+pub enum DiffLine {
+    Same(String),
+    Added(String),
+    Removed(String),
+}
+
+/// Line by line difference from `old` to `new`.
+pub fn line_diff(old: &str, new: &str) -> Vec<DiffLine> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    // most checks change a few lines, so diff only what lies between the
+    // common start and end
+    let prefix = old
+        .iter()
+        .zip(&new)
+        .take_while(|(old, new)| old == new)
+        .count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(old, new)| old == new)
+        .count();
+    let old_middle = &old[prefix..old.len() - suffix];
+    let new_middle = &new[prefix..new.len() - suffix];
+
+    let mut diff: Vec<DiffLine> = old[..prefix]
+        .iter()
+        .map(|line| DiffLine::Same(line.to_string()))
+        .collect();
+    if old_middle.len() * new_middle.len() <= MAX_EXACT_DIFF_CELLS {
+        diff.extend(lcs_diff(old_middle, new_middle));
+    } else {
+        diff.extend(
+            old_middle
+                .iter()
+                .map(|line| DiffLine::Removed(line.to_string())),
+        );
+        diff.extend(
+            new_middle
+                .iter()
+                .map(|line| DiffLine::Added(line.to_string())),
+        );
+    }
+    diff.extend(
+        old[old.len() - suffix..]
+            .iter()
+            .map(|line| DiffLine::Same(line.to_string())),
+    );
+    diff
+}
+
+fn lcs_diff(old: &[&str], new: &[&str]) -> Vec<DiffLine> {
+    // lengths[i][j]: longest common subsequence of old[i..] and new[j..]
+    let mut lengths = vec![vec![0u32; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i][j] = if old[i] == new[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut diff = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            diff.push(DiffLine::Same(old[i].to_string()));
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            diff.push(DiffLine::Removed(old[i].to_string()));
+            i += 1;
+        } else {
+            diff.push(DiffLine::Added(new[j].to_string()));
+            j += 1;
+        }
+    }
+    diff.extend(
+        old[i..]
+            .iter()
+            .map(|line| DiffLine::Removed(line.to_string())),
+    );
+    diff.extend(
+        new[j..]
+            .iter()
+            .map(|line| DiffLine::Added(line.to_string())),
+    );
+    diff
+}
+
+/// Number of (added, removed) lines, ignoring moves. Cheap enough to show
+/// for every row on every frame.
+pub fn count_changes(old: &str, new: &str) -> (usize, usize) {
+    let mut counts: HashMap<&str, isize> = HashMap::new();
+    for line in old.lines() {
+        *counts.entry(line).or_default() -= 1;
+    }
+    for line in new.lines() {
+        *counts.entry(line).or_default() += 1;
+    }
+    counts.values().fold((0, 0), |(added, removed), &count| {
+        if count > 0 {
+            (added + count as usize, removed)
+        } else {
+            (added, removed + count.unsigned_abs())
+        }
+    })
+}
+
+/// One line describing a change, followed by the first few changed lines,
+/// for notifications.
+pub fn summary(old: &str, new: &str) -> String {
+    const SHOWN_LINES: usize = 3;
+    let (added, removed) = count_changes(old, new);
+    let mut summary = format!("+{} -{} lines", added, removed);
+    let changed: Vec<DiffLine> = line_diff(old, new)
+        .into_iter()
+        .filter(|line| !matches!(line, DiffLine::Same(_)))
+        .collect();
+    for line in changed.iter().take(SHOWN_LINES) {
+        match line {
+            DiffLine::Added(text) => summary.push_str(&format!("\n+ {}", text.trim())),
+            DiffLine::Removed(text) => summary.push_str(&format!("\n- {}", text.trim())),
+            DiffLine::Same(_) => {}
+        }
+    }
+    if changed.len() > SHOWN_LINES {
+        summary.push_str(&format!("\n(and {} more)", changed.len() - SHOWN_LINES));
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn same(line: &str) -> DiffLine {
+        DiffLine::Same(line.to_owned())
+    }
+
+    fn added(line: &str) -> DiffLine {
+        DiffLine::Added(line.to_owned())
+    }
+
+    fn removed(line: &str) -> DiffLine {
+        DiffLine::Removed(line.to_owned())
+    }
+
+    #[test]
+    fn unchanged_start_and_end_are_kept() {
+        let diff = line_diff("a\nb\nc\nd\ne", "a\nb\nX\nd\ne");
+        assert!(
+            diff == [
+                same("a"),
+                same("b"),
+                removed("c"),
+                added("X"),
+                same("d"),
+                same("e")
+            ]
+        );
+    }
+
+    #[test]
+    fn moved_lines_are_diffed_exactly() {
+        let diff = line_diff("a\nb\nc", "b\nc\na");
+        assert!(diff == [removed("a"), same("b"), same("c"), added("a")]);
+    }
+
+    #[test]
+    fn empty_inputs_and_trailing_newlines() {
+        assert!(line_diff("", "").is_empty());
+        assert!(line_diff("", "a\nb") == [added("a"), added("b")]);
+        assert!(line_diff("a\nb", "") == [removed("a"), removed("b")]);
+        // lines() ignores a final newline, so adding one isn't a change
+        assert!(line_diff("a\nb", "a\nb\n") == [same("a"), same("b")]);
+        assert_eq!(count_changes("a\nb", "a\nb\n"), (0, 0));
+    }
+
+    #[test]
+    fn large_changes_fall_back_to_removing_and_adding() {
+        // past the limit of line pairs, so no lines are matched inside
+        let old: Vec<String> = (0..2001).map(|i| format!("old {}", i)).collect();
+        let mut new: Vec<String> = (0..2001).map(|i| format!("new {}", i)).collect();
+        new[1000] = "old 0".to_owned();
+        let (old, new) = (
+            format!("top\n{}\nbottom", old.join("\n")),
+            format!("top\n{}\nbottom", new.join("\n")),
+        );
+        let diff = line_diff(&old, &new);
+        assert_eq!(diff.len(), 2 + 2001 * 2);
+        assert!(diff[0] == same("top") && diff[diff.len() - 1] == same("bottom"));
+        assert!(diff[1..2002]
+            .iter()
+            .all(|line| matches!(line, DiffLine::Removed(_))));
+        assert!(diff[2002..4003]
+            .iter()
+            .all(|line| matches!(line, DiffLine::Added(_))));
+    }
+
+    #[test]
+    fn changes_are_counted_ignoring_moves() {
+        assert_eq!(count_changes("a\nb\nc", "c\na\nb"), (0, 0));
+        assert_eq!(count_changes("a\nb", "a\nB\nC"), (2, 1));
+        assert_eq!(count_changes("a\na", "a"), (0, 1));
+    }
+
+    #[test]
+    fn summary_lists_the_first_changes() {
+        assert_eq!(summary("a\nb", "a\n  c  "), "+1 -1 lines\n- b\n+ c");
+        let summary = summary("", "1\n2\n3\n4\n5");
+        assert_eq!(summary, "+5 -0 lines\n+ 1\n+ 2\n+ 3\n(and 2 more)");
+    }
+}
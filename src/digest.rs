@@ -0,0 +1,393 @@
+use chrono::{DateTime, Local, Utc};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use crate::app::{RowMode, ValueData};
+use crate::notification::{Channel, Notifier};
+
+const DEFAULT_DIGEST_SCHEDULE: &str = "0 0 8 * * *";
+
+#[derive(serde::Deserialize, serde::Serialize, PartialEq, Clone, Copy)]
+pub enum DigestFormat {
+    PlainText,
+    Markdown,
+    Html,
+}
+
+impl DigestFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DigestFormat::PlainText => "Plain text",
+            DigestFormat::Markdown => "Markdown",
+            DigestFormat::Html => "HTML",
+        }
+    }
+
+    pub fn get_formats() -> [DigestFormat; 3] {
+        [
+            DigestFormat::PlainText,
+            DigestFormat::Markdown,
+            DigestFormat::Html,
+        ]
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
+#[serde(default)]
+pub struct DigestSettings {
+    pub enabled: bool,
+    /// Cron expression (with seconds) in local time, e.g. "0 0 8 * * *" for every day at 08:00.
+    pub schedule: String,
+    pub format: DigestFormat,
+    pub notify: bool,
+    pub channel: Channel,
+    pub write_to_file: bool,
+    pub file_path: String,
+}
+
+impl Default for DigestSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            schedule: DEFAULT_DIGEST_SCHEDULE.to_owned(),
+            format: DigestFormat::PlainText,
+            notify: true,
+            channel: Channel::Desktop,
+            write_to_file: false,
+            file_path: String::new(),
+        }
+    }
+}
+
+/// What a row looked like at the time of the previous digest.
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
+struct Snapshot {
+    value: String,
+    lowest_value: Option<f64>,
+    highest_value: Option<f64>,
+}
+
+struct DigestRow {
+    name: String,
+    link: String,
+    value: String,
+    previous_value: Option<String>,
+    new_low: bool,
+    new_high: bool,
+    failing: bool,
+}
+
+impl DigestRow {
+    fn change(&self) -> String {
+        match &self.previous_value {
+            Some(previous) if *previous != self.value => format!("{} -> {}", previous, self.value),
+            Some(_) => "unchanged".to_owned(),
+            None => "new".to_owned(),
+        }
+    }
+
+    fn notes(&self) -> String {
+        let mut notes = Vec::new();
+        if self.failing {
+            notes.push("failing");
+        }
+        if self.new_low {
+            notes.push("new low");
+        }
+        if self.new_high {
+            notes.push("new high");
+        }
+        notes.join(", ")
+    }
+}
+
+/// The rows as of the previous digest, saved with the app so restarts don't
+/// reset "change since last digest".
+#[derive(serde::Deserialize, serde::Serialize, Default)]
+#[serde(default)]
+pub struct DigestState {
+    /// When the snapshots were taken, `None` before the first digest.
+    taken_at: Option<DateTime<Utc>>,
+    snapshots: HashMap<String, Snapshot>,
+}
+
+pub type SharedDigestState = Arc<Mutex<DigestState>>;
+
+/// Summarizes all tracked rows relative to the previous digest.
+/// The first digest ever compares against the values when digests were set up.
+pub struct Digest {
+    settings: DigestSettings,
+    state: SharedDigestState,
+}
+
+impl Digest {
+    pub fn new(
+        settings: DigestSettings,
+        state: SharedDigestState,
+        table_data: &[ValueData],
+    ) -> Self {
+        let digest = Self { settings, state };
+        if digest.state.lock().unwrap().taken_at.is_none() {
+            digest.take_snapshots(table_data);
+        }
+        digest
+    }
+
+    /// Build the report in the configured format and deliver it. Notifications
+    /// go through `notifier`, so they wait for quiet hours to end.
+    pub fn send(
+        &mut self,
+        table_data: &[ValueData],
+        notifier: &mut Notifier,
+        now: DateTime<Local>,
+    ) {
+        let rows = self.digest_rows(table_data);
+        if self.settings.notify {
+            notifier.send_message(
+                self.settings.channel,
+                render(&rows, DigestFormat::PlainText, now),
+                now,
+            );
+        }
+        if self.settings.write_to_file {
+            let report = render(&rows, self.settings.format, now);
+            if let Err(e) = std::fs::write(&self.settings.file_path, report) {
+                eprintln!(
+                    "Failed to write digest to {:?}: {:?}",
+                    self.settings.file_path, e
+                );
+            }
+        }
+        self.take_snapshots(table_data);
+    }
+
+    fn take_snapshots(&self, table_data: &[ValueData]) {
+        let mut state = self.state.lock().unwrap();
+        state.taken_at = Some(Utc::now());
+        state.snapshots = table_data
+            .iter()
+            .map(|row| {
+                let snapshot = Snapshot {
+                    value: row.latest_value.clone(),
+                    lowest_value: row.lowest_value,
+                    highest_value: row.highest_value,
+                };
+                (row.id.clone(), snapshot)
+            })
+            .collect();
+    }
+
+    fn digest_rows(&self, table_data: &[ValueData]) -> Vec<DigestRow> {
+        let state = self.state.lock().unwrap();
+        table_data
+            .iter()
+            .map(|row| {
+                let snapshot = state.snapshots.get(&row.id);
+                let current = crate::parse_number(&row.latest_value);
+                let new_low = matches!(
+                    (current, row.lowest_value, snapshot.and_then(|s| s.lowest_value)),
+                    (Some(current), Some(lowest), Some(previous_lowest))
+                        if current == lowest && lowest < previous_lowest
+                );
+                let new_high = matches!(
+                    (current, row.highest_value, snapshot.and_then(|s| s.highest_value)),
+                    (Some(current), Some(highest), Some(previous_highest))
+                        if current == highest && highest > previous_highest
+                );
+                let previous_value = snapshot.map(|s| match row.mode {
+                    RowMode::Value | RowMode::Availability(_) | RowMode::Computed { .. } => {
+                        s.value.clone()
+                    }
+                    RowMode::Text { .. } if s.value == row.latest_value => {
+                        row.display_value(&s.value)
+                    }
+                    // texts are too long to list, describe the change instead
+                    RowMode::Text { .. } => {
+                        let (added, removed) =
+                            crate::diff::count_changes(&s.value, &row.latest_value);
+                        format!("+{} -{} lines", added, removed)
+                    }
+                });
+                DigestRow {
+                    name: row.name.clone(),
+                    link: row.link.clone(),
+                    value: row.display_value(&row.latest_value),
+                    previous_value,
+                    new_low,
+                    new_high,
+                    failing: row.latest_value.is_empty(),
+                }
+            })
+            .collect()
+    }
+}
+
+fn render(rows: &[DigestRow], format: DigestFormat, now: DateTime<Local>) -> String {
+    let title = format!(
+        "Web value tracker digest, {}",
+        crate::format_date_time(&now)
+    );
+    let failing_count = rows.iter().filter(|row| row.failing).count();
+    let mut report = String::new();
+    match format {
+        DigestFormat::PlainText => {
+            report.push_str(&format!("{}\n", title));
+            report.push_str(&format!(
+                "{} rows, {} failing\n\n",
+                rows.len(),
+                failing_count
+            ));
+            for row in rows {
+                report.push_str(&format!("{}: {} ({})", row.name, row.value, row.change()));
+                let notes = row.notes();
+                if !notes.is_empty() {
+                    report.push_str(&format!(" [{}]", notes));
+                }
+                report.push('\n');
+            }
+        }
+        DigestFormat::Markdown => {
+            report.push_str(&format!("# {}\n\n", title));
+            report.push_str(&format!(
+                "{} rows, {} failing\n\n",
+                rows.len(),
+                failing_count
+            ));
+            report.push_str("| Name | Value | Change | Notes |\n");
+            report.push_str("|------|-------|--------|-------|\n");
+            for row in rows {
+                report.push_str(&format!(
+                    "| [{}]({}) | {} | {} | {} |\n",
+                    escape_markdown(&row.name),
+                    escape_markdown_link(&row.link),
+                    escape_markdown(&row.value),
+                    escape_markdown(&row.change()),
+                    row.notes()
+                ));
+            }
+        }
+        DigestFormat::Html => {
+            report.push_str(&format!(
+                "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>{0}</title></head>\n<body>\n<h1>{0}</h1>\n",
+                escape_html(&title)
+            ));
+            report.push_str(&format!(
+                "<p>{} rows, {} failing</p>\n",
+                rows.len(),
+                failing_count
+            ));
+            report.push_str(
+                "<table>\n<tr><th>Name</th><th>Value</th><th>Change</th><th>Notes</th></tr>\n",
+            );
+            for row in rows {
+                report.push_str(&format!(
+                    "<tr><td><a href=\"{}\">{}</a></td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                    escape_html(&row.link),
+                    escape_html(&row.name),
+                    escape_html(&row.value),
+                    escape_html(&row.change()),
+                    row.notes()
+                ));
+            }
+            report.push_str("</table>\n</body>\n</html>\n");
+        }
+    }
+    report
+}
+
+/// Escape what would end a table cell or a link's text.
+fn escape_markdown(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('|', "\\|")
+        .replace('[', "\\[")
+        .replace(']', "\\]")
+}
+
+/// Percent-encode what would end a link's target or its table cell.
+fn escape_markdown_link(link: &str) -> String {
+    link.replace(' ', "%20")
+        .replace('(', "%28")
+        .replace(')', "%29")
+        .replace('|', "%7C")
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn now() -> DateTime<Local> {
+        Local.with_ymd_and_hms(2024, 5, 1, 8, 0, 0).unwrap()
+    }
+
+    fn digest_row(name: &str, link: &str, value: &str, previous_value: Option<&str>) -> DigestRow {
+        DigestRow {
+            name: name.to_owned(),
+            link: link.to_owned(),
+            value: value.to_owned(),
+            previous_value: previous_value.map(str::to_owned),
+            new_low: false,
+            new_high: false,
+            failing: false,
+        }
+    }
+
+    #[test]
+    fn plain_text_lists_changes_and_notes() {
+        let mut cheaper = digest_row("Book", "https://example.com", "$8", Some("$10"));
+        cheaper.new_low = true;
+        let rows = [
+            cheaper,
+            digest_row("Pen", "https://example.com", "$2", None),
+        ];
+        let report = render(&rows, DigestFormat::PlainText, now());
+        assert!(report.contains("2 rows, 0 failing"));
+        assert!(report.contains("Book: $8 ($10 -> $8) [new low]\n"));
+        assert!(report.contains("Pen: $2 (new)\n"));
+    }
+
+    #[test]
+    fn markdown_escapes_names_and_links() {
+        let rows = [digest_row(
+            "Lamp [white] | tall",
+            "https://example.com/lamp (white)",
+            "$30",
+            Some("$30"),
+        )];
+        let report = render(&rows, DigestFormat::Markdown, now());
+        assert!(report.contains(
+            "| [Lamp \\[white\\] \\| tall](https://example.com/lamp%20%28white%29) | $30 | unchanged |  |"
+        ));
+    }
+
+    #[test]
+    fn html_escapes_text_and_links() {
+        let rows = [digest_row(
+            "<b>Tom & Jerry</b>",
+            "https://example.com/?a=1&b=\"2\"",
+            "$5",
+            Some("$4"),
+        )];
+        let report = render(&rows, DigestFormat::Html, now());
+        assert!(report.contains("<a href=\"https://example.com/?a=1&amp;b=&quot;2&quot;\">"));
+        assert!(report.contains("&lt;b&gt;Tom &amp; Jerry&lt;/b&gt;"));
+        assert!(report.contains("<td>$4 -&gt; $5</td>"));
+    }
+
+    #[test]
+    fn empty_period_still_renders() {
+        for format in DigestFormat::get_formats() {
+            let report = render(&[], format, now());
+            assert!(report.contains("0 rows, 0 failing"));
+        }
+    }
+}
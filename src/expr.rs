@@ -0,0 +1,371 @@
+//! Arithmetic formulas over named values, e.g. `price + shipping` or
+//! `min("Shop A", "Shop B")`. Names with spaces or punctuation other than
+//! `_` and `.` are quoted.
+
+/// Deepest nesting of parentheses, calls and signs, so formulas sent through
+/// the API can't overflow the stack.
+const MAX_DEPTH: usize = 64;
+
+/// Evaluate `formula`, looking names up with `resolve`.
+///
+/// Supports `+ - * /`, parentheses, unary minus and the functions
+/// `min`, `max`, `sum`, `avg`, `abs` and `round(x, digits)`.
+pub fn evaluate(
+    formula: &str,
+    resolve: &dyn Fn(&str) -> Result<f64, String>,
+) -> Result<f64, String> {
+    let tokens = tokenize(formula)?;
+    let mut parser = Parser {
+        tokens: &tokens,
+        position: 0,
+        depth: 0,
+        resolve,
+    };
+    let value = parser.expression()?;
+    if let Some(token) = parser.peek() {
+        return Err(format!("unexpected {}", token.describe()));
+    }
+    if !value.is_finite() {
+        return Err("the result isn't a number, e.g. after dividing by zero".to_owned());
+    }
+    Ok(value)
+}
+
+/// Names `formula` refers to, without those of functions. Empty when it
+/// doesn't parse.
+pub fn names(formula: &str) -> Vec<String> {
+    let Ok(tokens) = tokenize(formula) else {
+        return Vec::new();
+    };
+    tokens
+        .iter()
+        .enumerate()
+        .filter_map(|(index, token)| match token {
+            Token::Name(name) if tokens.get(index + 1) != Some(&Token::OpenParen) => {
+                Some(name.clone())
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// A number as shown for computed values: whole numbers without decimals,
+/// others with two.
+pub fn format_number(value: f64) -> String {
+    if value.fract() == 0.0 {
+        format!("{}", value)
+    } else {
+        format!("{:.2}", value)
+    }
+}
+
+#[derive(Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Name(String),
+    Operator(char),
+    OpenParen,
+    CloseParen,
+    Comma,
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Number(number) => format!("number {}", number),
+            Token::Name(name) => format!("name {:?}", name),
+            Token::Operator(operator) => format!("{:?}", operator),
+            Token::OpenParen => "\"(\"".to_owned(),
+            Token::CloseParen => "\")\"".to_owned(),
+            Token::Comma => "\",\"".to_owned(),
+        }
+    }
+}
+
+fn tokenize(formula: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = formula.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '0'..='9' | '.' => {
+                let mut number = String::new();
+                while let Some(&c) = chars.peek().filter(|c| c.is_ascii_digit() || **c == '.') {
+                    number.push(c);
+                    chars.next();
+                }
+                let number = number
+                    .parse()
+                    .map_err(|_| format!("{:?} isn't a number", number))?;
+                tokens.push(Token::Number(number));
+            }
+            '"' => {
+                chars.next();
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some(c) => name.push(c),
+                        None => return Err(format!("\"{}\" is missing its closing quote", name)),
+                    }
+                }
+                tokens.push(Token::Name(name));
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut name = String::new();
+                while let Some(&c) = chars
+                    .peek()
+                    .filter(|c| c.is_alphanumeric() || **c == '_' || **c == '.')
+                {
+                    name.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::Name(name));
+            }
+            '+' | '-' | '*' | '/' => {
+                tokens.push(Token::Operator(c));
+                chars.next();
+            }
+            '(' => {
+                tokens.push(Token::OpenParen);
+                chars.next();
+            }
+            ')' => {
+                tokens.push(Token::CloseParen);
+                chars.next();
+            }
+            ',' => {
+                tokens.push(Token::Comma);
+                chars.next();
+            }
+            c => return Err(format!("unexpected {:?}", c)),
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: &'a [Token],
+    position: usize,
+    depth: usize,
+    resolve: &'a dyn Fn(&str) -> Result<f64, String>,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), String> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            Some(token) => Err(format!(
+                "expected {}, found {}",
+                expected.describe(),
+                token.describe()
+            )),
+            None => Err(format!("expected {} at the end", expected.describe())),
+        }
+    }
+
+    fn expression(&mut self) -> Result<f64, String> {
+        let mut value = self.term()?;
+        while let Some(Token::Operator(operator @ ('+' | '-'))) = self.peek().cloned() {
+            self.position += 1;
+            let right = self.term()?;
+            value = if operator == '+' {
+                value + right
+            } else {
+                value - right
+            };
+        }
+        Ok(value)
+    }
+
+    fn term(&mut self) -> Result<f64, String> {
+        let mut value = self.factor()?;
+        while let Some(Token::Operator(operator @ ('*' | '/'))) = self.peek().cloned() {
+            self.position += 1;
+            let right = self.factor()?;
+            value = if operator == '*' {
+                value * right
+            } else {
+                value / right
+            };
+        }
+        Ok(value)
+    }
+
+    fn factor(&mut self) -> Result<f64, String> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(format!(
+                "the formula nests deeper than {} levels",
+                MAX_DEPTH
+            ));
+        }
+        let value = self.nested_factor();
+        self.depth -= 1;
+        value
+    }
+
+    fn nested_factor(&mut self) -> Result<f64, String> {
+        match self.next() {
+            Some(Token::Number(number)) => Ok(number),
+            Some(Token::Operator('-')) => Ok(-self.factor()?),
+            Some(Token::OpenParen) => {
+                let value = self.expression()?;
+                self.expect(Token::CloseParen)?;
+                Ok(value)
+            }
+            Some(Token::Name(name)) if self.peek() == Some(&Token::OpenParen) => {
+                self.position += 1;
+                let arguments = self.arguments()?;
+                call(&name, &arguments)
+            }
+            Some(Token::Name(name)) => (self.resolve)(&name),
+            Some(token) => Err(format!("unexpected {}", token.describe())),
+            None => Err("the formula ends too early".to_owned()),
+        }
+    }
+
+    /// Comma separated expressions up to the closing parenthesis.
+    fn arguments(&mut self) -> Result<Vec<f64>, String> {
+        let mut arguments = Vec::new();
+        if self.peek() == Some(&Token::CloseParen) {
+            self.position += 1;
+            return Ok(arguments);
+        }
+        loop {
+            arguments.push(self.expression()?);
+            match self.next() {
+                Some(Token::Comma) => {}
+                Some(Token::CloseParen) => return Ok(arguments),
+                Some(token) => return Err(format!("unexpected {}", token.describe())),
+                None => return Err("missing \")\"".to_owned()),
+            }
+        }
+    }
+}
+
+fn call(function: &str, arguments: &[f64]) -> Result<f64, String> {
+    let needs_arguments = || {
+        if arguments.is_empty() {
+            Err(format!("{}() needs at least one value", function))
+        } else {
+            Ok(())
+        }
+    };
+    match function.to_lowercase().as_str() {
+        "min" => needs_arguments().map(|_| arguments.iter().copied().fold(f64::INFINITY, f64::min)),
+        "max" => {
+            needs_arguments().map(|_| arguments.iter().copied().fold(f64::NEG_INFINITY, f64::max))
+        }
+        "sum" => Ok(arguments.iter().sum()),
+        "avg" => needs_arguments().map(|_| arguments.iter().sum::<f64>() / arguments.len() as f64),
+        "abs" => match arguments {
+            [value] => Ok(value.abs()),
+            _ => Err("abs() takes one value".to_owned()),
+        },
+        "round" => match arguments {
+            [value] => Ok(value.round()),
+            [value, digits] => {
+                let factor = 10f64.powi(*digits as i32);
+                Ok((value * factor).round() / factor)
+            }
+            _ => Err("round() takes a value and optionally the number of digits".to_owned()),
+        },
+        _ => Err(format!("unknown function {}()", function)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolve(name: &str) -> Result<f64, String> {
+        match name {
+            "price" => Ok(10.0),
+            "Shop A" => Ok(4.0),
+            "shop.b" => Ok(6.0),
+            _ => Err(format!("no row named {:?}", name)),
+        }
+    }
+
+    fn eval(formula: &str) -> Result<f64, String> {
+        evaluate(formula, &resolve)
+    }
+
+    #[test]
+    fn precedence_and_parentheses() {
+        assert_eq!(eval("1 + 2 * 3"), Ok(7.0));
+        assert_eq!(eval("(1 + 2) * 3"), Ok(9.0));
+        assert_eq!(eval("10 - 4 - 3"), Ok(3.0));
+        assert_eq!(eval("12 / 3 / 2"), Ok(2.0));
+        assert_eq!(eval("-2 * -price"), Ok(20.0));
+    }
+
+    #[test]
+    fn names_and_functions() {
+        assert_eq!(eval("min(\"Shop A\", shop.b) + price"), Ok(14.0));
+        assert_eq!(eval("avg(\"Shop A\", shop.b)"), Ok(5.0));
+        assert_eq!(eval("round(2 / 3, 2)"), Ok(0.67));
+        assert_eq!(eval("MAX(1, 2)"), Ok(2.0));
+    }
+
+    #[test]
+    fn errors() {
+        assert!(eval("price +").is_err());
+        assert!(eval("(1 + 2").is_err());
+        assert!(eval("1 2").is_err());
+        assert!(eval("price / 0").is_err());
+        assert!(eval("min()").is_err());
+        assert!(eval("nope(1)").is_err());
+        assert_eq!(eval("missing"), Err("no row named \"missing\"".to_owned()));
+    }
+
+    #[test]
+    fn unterminated_name_is_an_error() {
+        assert!(eval("\"Shop A").is_err());
+        assert!(eval("price + \"Shop A\" + \"").is_err());
+    }
+
+    #[test]
+    fn deep_nesting_is_an_error() {
+        assert_eq!(
+            eval(&format!("{}1{}", "(".repeat(60), ")".repeat(60))),
+            Ok(1.0)
+        );
+        assert!(eval(&format!("{}1{}", "(".repeat(100_000), ")".repeat(100_000))).is_err());
+        assert!(eval(&format!("{}1", "-".repeat(100_000))).is_err());
+        assert!(eval(&format!(
+            "{}1{}",
+            "abs(".repeat(100_000),
+            ")".repeat(100_000)
+        ))
+        .is_err());
+    }
+
+    #[test]
+    fn lists_names_but_not_functions() {
+        assert_eq!(
+            names("min(\"Shop A\", shop.b) + price * 2"),
+            ["Shop A", "shop.b", "price"]
+        );
+        assert!(names("\"Shop A").is_empty());
+    }
+
+    #[test]
+    fn formats_numbers() {
+        assert_eq!(format_number(3.0), "3");
+        assert_eq!(format_number(2.5), "2.50");
+    }
+}
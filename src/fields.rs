@@ -0,0 +1,184 @@
+use egui::{TextEdit, Ui};
+use headless_chrome::Tab;
+use std::error::Error;
+
+/// Name the row's own value goes by in formulas.
+pub const VALUE_NAME: &str = "value";
+
+/// A further value read from the same page load as the row's own, e.g. the
+/// shipping cost next to a price.
+#[derive(serde::Deserialize, serde::Serialize, schemars::JsonSchema, Clone, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Field {
+    pub name: String,
+    /// Where the value is read from, unused when `formula` is set.
+    pub selector: String,
+    /// Computes the value from the row's `value` and the fields above this
+    /// one instead, e.g. `value + shipping`.
+    #[serde(default)]
+    pub formula: String,
+    /// Latest value, empty when it couldn't be read.
+    #[serde(default)]
+    pub value: String,
+}
+
+/// Read every field from the page `tab` is on, in order, given the row's
+/// own `value`. Fields that can't be read or computed are left empty.
+pub fn read_fields(tab: &Tab, value: &str, fields: &[Field]) -> Vec<String> {
+    field_values(value, fields, |selector| crate::read_text(tab, selector))
+}
+
+fn field_values(
+    value: &str,
+    fields: &[Field],
+    mut read: impl FnMut(&str) -> Result<String, Box<dyn Error>>,
+) -> Vec<String> {
+    let mut values: Vec<String> = Vec::with_capacity(fields.len());
+    for field in fields {
+        let field_value = if field.formula.trim().is_empty() {
+            read(&field.selector).unwrap_or_else(|e| {
+                println!("Couldn't read field {:?}: {}", field.name, e);
+                String::new()
+            })
+        } else {
+            let resolve = |name: &str| {
+                let text = if name == VALUE_NAME {
+                    Some(value)
+                } else {
+                    fields
+                        .iter()
+                        .zip(&values)
+                        .find(|(field, _)| field.name == name)
+                        .map(|(_, value)| value.as_str())
+                };
+                let text = text.ok_or_else(|| format!("no field named {:?} above", name))?;
+                crate::parse_number(text).ok_or_else(|| format!("{:?} has no number", name))
+            };
+            match crate::expr::evaluate(&field.formula, &resolve) {
+                Ok(number) => crate::expr::format_number(number),
+                Err(e) => {
+                    println!("Couldn't compute field {:?}: {}", field.name, e);
+                    String::new()
+                }
+            }
+        };
+        values.push(field_value);
+    }
+    values
+}
+
+/// Why `fields` can't be read, if they can't.
+pub fn validate_fields(fields: &[Field]) -> Result<(), String> {
+    match fields
+        .iter()
+        .find(|field| field.selector.trim().is_empty() && field.formula.trim().is_empty())
+    {
+        Some(field) => Err(format!(
+            "field {:?} needs a css selector or a formula",
+            field.name
+        )),
+        None => Ok(()),
+    }
+}
+
+/// "name: value" lines, for showing the fields next to the row's value.
+pub fn summary(fields: &[Field]) -> String {
+    fields
+        .iter()
+        .map(|field| format!("{}: {}", field.name, field.value))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Editable list of fields.
+pub fn fields_ui(ui: &mut Ui, fields: &mut Vec<Field>) {
+    let mut removed = None;
+    for (index, field) in fields.iter_mut().enumerate() {
+        ui.horizontal(|ui| {
+            ui.add(
+                TextEdit::singleline(&mut field.name)
+                    .hint_text("name, e.g. shipping")
+                    .desired_width(100.0),
+            );
+            ui.add_enabled(
+                field.formula.is_empty(),
+                TextEdit::singleline(&mut field.selector)
+                    .hint_text("css selector")
+                    .desired_width(160.0),
+            );
+            ui.add(
+                TextEdit::singleline(&mut field.formula)
+                    .hint_text("or formula, e.g. value + shipping")
+                    .desired_width(180.0),
+            );
+            if !field.value.is_empty() {
+                ui.label(format!("= {}", field.value));
+            }
+            if ui.small_button("🗑").clicked() {
+                removed = Some(index);
+            }
+        });
+    }
+    if let Err(error) = validate_fields(fields) {
+        ui.colored_label(egui::Color32::RED, error);
+    }
+    if let Some(index) = removed {
+        fields.remove(index);
+    }
+    if ui.small_button("➕ Add field").clicked() {
+        fields.push(Field::default());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(name: &str, selector: &str, formula: &str) -> Field {
+        Field {
+            name: name.to_owned(),
+            selector: selector.to_owned(),
+            formula: formula.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn fields_are_read_and_computed_in_order() {
+        let fields = [
+            field("shipping", ".shipping", ""),
+            field("missing", ".gone", ""),
+            field("total", "", "value + shipping"),
+            field("broken", "", "value + missing"),
+            field("early", "", "later * 2"),
+            field("later", ".later", ""),
+        ];
+        let mut read_selectors = Vec::new();
+        let values = field_values("$10.50", &fields, |selector| {
+            read_selectors.push(selector.to_owned());
+            match selector {
+                ".gone" => Err("no element".into()),
+                ".later" => Ok("3".to_owned()),
+                _ => Ok("+ $4.50 shipping".to_owned()),
+            }
+        });
+        assert_eq!(values, ["+ $4.50 shipping", "", "15", "", "", "3"]);
+        assert_eq!(read_selectors, [".shipping", ".gone", ".later"]);
+    }
+
+    #[test]
+    fn fields_need_a_selector_or_formula() {
+        assert!(validate_fields(&[]).is_ok());
+        assert!(validate_fields(&[field("a", ".a", ""), field("b", "", "a * 2")]).is_ok());
+        let error = validate_fields(&[field("a", ".a", ""), field("b", " ", "")]).unwrap_err();
+        assert!(error.contains("\"b\""), "{}", error);
+    }
+
+    #[test]
+    fn summary_lists_fields() {
+        let mut fields = [field("shipping", ".s", ""), field("total", "", "value")];
+        fields[0].value = "4.5".to_owned();
+        assert_eq!(summary(&fields), "shipping: 4.5\ntotal: ");
+        assert_eq!(summary(&[]), "");
+    }
+}
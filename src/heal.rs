@@ -0,0 +1,217 @@
+use headless_chrome::Tab;
+use std::error::Error;
+
+use crate::picker::ROBUST_SELECTOR_JS;
+
+/// Candidates scoring below this aren't proposed, to avoid suggesting unrelated elements.
+const MIN_HEAL_SCORE: f64 = 5.0;
+
+/// What the element a row's selector matched looked like, so it can be found
+/// again when a redesign breaks the selector.
+#[derive(serde::Deserialize, serde::Serialize, schemars::JsonSchema, Clone, Default)]
+#[serde(default)]
+pub struct Fingerprint {
+    pub tag: String,
+    pub id: String,
+    pub classes: Vec<String>,
+    /// Attributes other than id and class, as (name, value).
+    pub attributes: Vec<(String, String)>,
+    /// Tag names from the body down to the element, e.g. "div > span:nth-of-type(2)".
+    pub path: String,
+    pub text: String,
+    /// Text next to the element, usually a label like "Price:".
+    pub neighbor_text: String,
+}
+
+/// A selector found by healing, waiting for the user to accept it.
+#[derive(serde::Deserialize, serde::Serialize, schemars::JsonSchema, Clone)]
+pub struct SelectorSuggestion {
+    pub selector: String,
+    /// Value the suggested selector reads right now.
+    pub value: String,
+}
+
+const FINGERPRINT_JS: &str = r##"
+const normalize = (text) => (text ?? '').replace(/\s+/g, ' ').trim();
+const structuralPath = (element) => {
+    const parts = [];
+    for (let node = element; node && node !== document.body && node !== document.documentElement; node = node.parentElement) {
+        const siblings = node.parentElement
+            ? [...node.parentElement.children].filter((sibling) => sibling.localName === node.localName)
+            : [];
+        parts.unshift(siblings.length > 1
+            ? `${node.localName}:nth-of-type(${siblings.indexOf(node) + 1})`
+            : node.localName);
+    }
+    return parts.join(' > ');
+};
+const neighborText = (element) => {
+    const sibling = element.previousElementSibling;
+    const text = normalize(sibling ? sibling.textContent : '');
+    if (text) {
+        return text.slice(0, 100);
+    }
+    const parent = element.parentElement;
+    return parent ? normalize(parent.textContent.replace(element.textContent, '')).slice(0, 100) : '';
+};
+"##;
+
+/// Record the fingerprint of the first element matching `css_selector`.
+pub fn capture(tab: &Tab, css_selector: &str) -> Result<Option<Fingerprint>, Box<dyn Error>> {
+    let js = format!(
+        r##"
+(() => {{
+    {}
+    const element = document.querySelector({:?});
+    if (!element) {{
+        return null;
+    }}
+    return JSON.stringify({{
+        tag: element.localName,
+        id: element.id,
+        classes: [...element.classList],
+        attributes: [...element.attributes]
+            .filter((attribute) => attribute.name !== 'id' && attribute.name !== 'class')
+            .map((attribute) => [attribute.name, attribute.value]),
+        path: structuralPath(element),
+        text: normalize(element.textContent),
+        neighbor_text: neighborText(element),
+    }});
+}})()
+"##,
+        FINGERPRINT_JS, css_selector
+    );
+    parse_result(tab.evaluate(&js, false)?.value.as_ref())
+}
+
+/// Look for the element best matching `fingerprint` and propose a selector for it.
+pub fn suggest_selector(
+    tab: &Tab,
+    fingerprint: &Fingerprint,
+) -> Result<Option<SelectorSuggestion>, Box<dyn Error>> {
+    parse_result(
+        tab.evaluate(&suggest_js(fingerprint)?, false)?
+            .value
+            .as_ref(),
+    )
+}
+
+/// Script scoring every element of the page against `fingerprint`,
+/// returning the best one's selector and text as JSON, or null.
+fn suggest_js(fingerprint: &Fingerprint) -> Result<String, serde_json::Error> {
+    Ok(format!(
+        r##"
+((fingerprint) => {{
+    {}
+    {}
+    let best = null;
+    let bestScore = 0;
+    for (const element of document.body.querySelectorAll('*')) {{
+        const text = normalize(element.textContent);
+        if (!text || text.length > 200) {{
+            continue;
+        }}
+        let score = 0;
+        if (element.localName === fingerprint.tag) score += 1;
+        if (fingerprint.id && element.id === fingerprint.id) score += 3;
+        if (fingerprint.classes.length) {{
+            const shared = fingerprint.classes.filter((name) => element.classList.contains(name));
+            score += 3 * shared.length / fingerprint.classes.length;
+        }}
+        for (const [name, value] of fingerprint.attributes) {{
+            if (element.getAttribute(name) === value) score += 2;
+        }}
+        if (text === fingerprint.text) score += 3;
+        if (fingerprint.neighbor_text && neighborText(element) === fingerprint.neighbor_text) score += 3;
+        if (structuralPath(element) === fingerprint.path) score += 2;
+        // prefer the element holding the text over its wrappers
+        if (element.children.length === 0) score += 1;
+        if (score > bestScore) {{
+            best = element;
+            bestScore = score;
+        }}
+    }}
+    if (!best || bestScore < {}) {{
+        return null;
+    }}
+    return JSON.stringify({{ selector: robustSelector(best), value: normalize(best.textContent) }});
+}})({})
+"##,
+        FINGERPRINT_JS,
+        ROBUST_SELECTOR_JS,
+        MIN_HEAL_SCORE,
+        serde_json::to_string(fingerprint)?
+    ))
+}
+
+/// What a script evaluated to: a JSON string to parse, or nothing.
+fn parse_result<T: serde::de::DeserializeOwned>(
+    value: Option<&serde_json::Value>,
+) -> Result<Option<T>, Box<dyn Error>> {
+    match value.and_then(|value| value.as_str()) {
+        Some(json) => Ok(Some(serde_json::from_str(json)?)),
+        None => Ok(None),
+    }
+}
+
+/// Point out selector parts that tend to break when a site changes.
+pub fn fragility_hint(css_selector: &str) -> Option<&'static str> {
+    if css_selector.contains(":nth-") {
+        Some("Depends on element positions, which often change. Prefer an id or attribute.")
+    } else if css_selector.matches('>').count() >= 4 {
+        Some("Long element paths break when the page layout changes.")
+    } else if css_selector
+        .split(['.', '#', ' ', '>'])
+        .any(|part| part.chars().filter(char::is_ascii_digit).count() >= 3)
+    {
+        Some("Looks like it uses generated names, which change between site updates.")
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn hints_for_fragile_selectors() {
+        assert!(fragility_hint("ul > li:nth-child(3) > span").is_some());
+        assert!(fragility_hint("main > div > div > section > span").is_some());
+        assert!(fragility_hint(".css-1x2y345 .price").is_some());
+        assert!(fragility_hint("#product-price").is_none());
+        assert!(fragility_hint("[data-testid=price] > span").is_none());
+    }
+
+    #[test]
+    fn suggestion_script_embeds_the_fingerprint() {
+        let fingerprint = Fingerprint {
+            tag: "span".to_owned(),
+            text: "it's \"$10\" </script>".to_owned(),
+            neighbor_text: "Price:".to_owned(),
+            ..Default::default()
+        };
+        let js = suggest_js(&fingerprint).unwrap();
+        let argument = js.trim_end().strip_suffix(')').unwrap();
+        let argument = &argument[argument.rfind("})(").unwrap() + 3..];
+        let embedded: Fingerprint = serde_json::from_str(argument).unwrap();
+        assert_eq!(embedded.text, fingerprint.text);
+        assert!(js.contains(&format!("bestScore < {}", MIN_HEAL_SCORE)));
+    }
+
+    #[test]
+    fn script_results_are_parsed() {
+        let suggestion: Option<SelectorSuggestion> =
+            parse_result(Some(&json!(r##"{"selector": "#price", "value": "$10"}"##))).unwrap();
+        let suggestion = suggestion.unwrap();
+        assert_eq!(suggestion.selector, "#price");
+        assert_eq!(suggestion.value, "$10");
+
+        let nothing: Option<SelectorSuggestion> = parse_result(Some(&json!(null))).unwrap();
+        assert!(nothing.is_none());
+        let missing: Option<Fingerprint> = parse_result(None).unwrap();
+        assert!(missing.is_none());
+        assert!(parse_result::<SelectorSuggestion>(Some(&json!("not json"))).is_err());
+    }
+}
@@ -0,0 +1,143 @@
+use std::{error::Error, process::Stdio, time::Duration};
+use tokio::{io::AsyncWriteExt, process::Command};
+
+use crate::notification::Alert;
+
+const HOOK_TIMEOUT: Duration = Duration::from_secs(30);
+/// Longest value passed in an environment variable. Text rows hold whole
+/// pages, and the OS refuses to start commands with very long variables.
+/// The JSON on stdin always has the full values.
+const MAX_ENV_VALUE_BYTES: usize = 8 * 1024;
+
+/// JSON written to the command's stdin.
+#[derive(serde::Serialize)]
+struct HookPayload<'a> {
+    id: &'a str,
+    name: &'a str,
+    url: &'a str,
+    old_value: &'a str,
+    new_value: &'a str,
+    changed_at: String,
+}
+
+/// Run a row's "on change" command, logging its output.
+pub fn run_on_change_command(command: &str, link: &str, alert: &Alert) {
+    let result = tokio::task::block_in_place(|| {
+        tokio::runtime::Handle::current().block_on(run_command(command, link, alert))
+    });
+    if let Err(e) = result {
+        eprintln!(
+            "On change command for {:?} failed: {:?}",
+            alert.name,
+            e.to_string()
+        );
+    }
+}
+
+async fn run_command(command: &str, link: &str, alert: &Alert) -> Result<(), Box<dyn Error>> {
+    let payload = serde_json::to_string(&HookPayload {
+        id: &alert.id,
+        name: &alert.name,
+        url: link,
+        old_value: &alert.old_value,
+        new_value: &alert.new_value,
+        changed_at: alert.changed_at.to_rfc3339(),
+    })?;
+
+    let mut child = shell_command(command)
+        .env("WVT_ID", &alert.id)
+        .env("WVT_NAME", &alert.name)
+        .env("WVT_URL", link)
+        .env("WVT_OLD_VALUE", env_value(&alert.old_value))
+        .env("WVT_NEW_VALUE", env_value(&alert.new_value))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()?;
+
+    let stdin = child.stdin.take();
+    let write_payload = async move {
+        if let Some(mut stdin) = stdin {
+            // The command may not read stdin at all, so a broken pipe is fine.
+            let _ = stdin.write_all(payload.as_bytes()).await;
+        }
+    };
+    // written while waiting, so a command that never reads a large payload
+    // still times out rather than blocking on a full pipe
+    let run = async {
+        let ((), output) = tokio::join!(write_payload, child.wait_with_output());
+        output
+    };
+    let output = tokio::time::timeout(HOOK_TIMEOUT, run)
+        .await
+        .map_err(|_| format!("timed out after {} seconds", HOOK_TIMEOUT.as_secs()))??;
+
+    println!(
+        "On change command for {:?} exited with {}\nstdout: {}\nstderr: {}",
+        alert.name,
+        output.status,
+        String::from_utf8_lossy(&output.stdout).trim(),
+        String::from_utf8_lossy(&output.stderr).trim()
+    );
+    Ok(())
+}
+
+/// `value` cut to `MAX_ENV_VALUE_BYTES` on a character boundary.
+fn env_value(value: &str) -> &str {
+    if value.len() <= MAX_ENV_VALUE_BYTES {
+        return value;
+    }
+    let end = (0..=MAX_ENV_VALUE_BYTES)
+        .rev()
+        .find(|&index| value.is_char_boundary(index))
+        .unwrap_or(0);
+    &value[..end]
+}
+
+#[cfg(windows)]
+fn shell_command(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.arg("/C").arg(command);
+    shell
+}
+
+#[cfg(not(windows))]
+fn shell_command(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command);
+    shell
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::RowMode;
+
+    #[test]
+    fn env_values_are_cut_on_a_character_boundary() {
+        assert_eq!(env_value("$10"), "$10");
+        let long = "é".repeat(MAX_ENV_VALUE_BYTES);
+        let cut = env_value(&long);
+        assert!(cut.len() <= MAX_ENV_VALUE_BYTES);
+        assert!(cut.len() > MAX_ENV_VALUE_BYTES - 2);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn whole_pages_reach_commands_that_ignore_stdin() {
+        let page = "line of a long page\n".repeat(50_000);
+        let alert = Alert {
+            id: "id".to_owned(),
+            name: "Terms".to_owned(),
+            old_value: page.clone(),
+            new_value: page + "one more line\n",
+            changed_at: chrono::Utc::now(),
+            mode: RowMode::Text { html: false },
+            screenshot: None,
+        };
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let result = runtime.block_on(run_command("true", "https://example.com", &alert));
+        assert!(result.is_ok());
+    }
+}
@@ -1,25 +1,268 @@
+mod api;
 mod app;
+mod availability;
+mod browser;
+mod diff;
+mod digest;
+mod expr;
+mod fields;
+mod heal;
+mod hook;
+mod metrics;
+mod mqtt;
+mod notification;
+mod picker;
+mod preset;
+mod screenshot;
+mod script;
+mod session;
+mod steps;
+mod timestamp;
 pub use app::ThisApp;
 
-use headless_chrome::{Browser, LaunchOptions};
+/// Window title, also used by eframe to name the storage directory.
+pub const APP_NAME: &str = "Web value tracker";
+
+use app::RowMode;
+use notification::{Alert, Notifier};
 use notify_rust::{Notification, Timeout};
 use poll_promise::Promise;
-use std::{collections::VecDeque, error::Error};
+use regex::Regex;
+use std::{
+    collections::{HashMap, VecDeque},
+    error::Error,
+    sync::{Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+
+const MAX_HISTORY_ENTRIES: usize = 1000;
+/// Text rows keep whole pages in their history, and all of it is saved again
+/// on every autosave, so they keep fewer.
+const MAX_TEXT_HISTORY_ENTRIES: usize = 50;
+const TEXT_POLL_INTERVAL: Duration = Duration::from_millis(250);
 
-async fn get_current_value(url: &str, css_selector: &str) -> Result<String, Box<dyn Error>> {
-    let browser = Browser::new(LaunchOptions {
-        headless: true,
+/// What loading a row's page produced.
+#[derive(Clone, Default)]
+struct FetchResult {
+    /// Empty when the value couldn't be read.
+    value: String,
+    fingerprint: Option<heal::Fingerprint>,
+    /// Set when the selector no longer matched but a similar element was found.
+    suggestion: Option<heal::SelectorSuggestion>,
+    /// Index into `ValueData::selectors` of the one the value was read with.
+    matched_selector: Option<usize>,
+    /// The browser's cookies after the fetch, for rows that keep a cookie jar.
+    cookies: Option<Vec<session::StoredCookie>>,
+    /// Why the fetch failed, if it did.
+    error: Option<String>,
+    /// Path of a picture of the page, taken on a change or failure.
+    screenshot: Option<String>,
+    /// Values of the row's fields, in order.
+    fields: Vec<String>,
+}
+
+async fn get_current_value(row: &crate::app::ValueData) -> FetchResult {
+    let started = Instant::now();
+    let result = load_current_value(row).await;
+    metrics::record_fetch_duration(started.elapsed());
+    result
+}
+
+async fn load_current_value(row: &crate::app::ValueData) -> FetchResult {
+    let settings = browser::effective_settings(&row.browser);
+    let (_browser, tab) = match browser::launch(&settings, true) {
+        Ok(launched) => launched,
+        Err(e) => return failed_fetch(row, e, None),
+    };
+    let screenshots = screenshot::settings();
+    match load_page_value(row, &tab, &settings) {
+        Ok(mut result) => {
+            // a first value isn't a change, and drafts in the add dialog have none yet
+            let changed = !result.value.is_empty()
+                && !row.latest_value.is_empty()
+                && result.value != row.latest_value;
+            if changed && screenshots.on_change {
+                let element = result
+                    .matched_selector
+                    .filter(|_| !screenshots.full_page)
+                    .and_then(|index| row.selectors().get(index).copied());
+                result.screenshot = screenshot::take(&tab, &row.id, element);
+            } else if result.value.is_empty() && screenshots.on_failure {
+                result.screenshot = screenshot::take(&tab, &row.id, None);
+            }
+            result
+        }
+        Err(e) => {
+            let screenshot = screenshots
+                .on_failure
+                .then(|| screenshot::take(&tab, &row.id, None))
+                .flatten();
+            failed_fetch(row, e, screenshot)
+        }
+    }
+}
+
+fn failed_fetch(
+    row: &crate::app::ValueData,
+    error: Box<dyn Error>,
+    screenshot: Option<String>,
+) -> FetchResult {
+    eprintln!("Failed to fetch {:?}: {}", row.name, error);
+    FetchResult {
+        error: Some(error.to_string()),
+        screenshot,
         ..Default::default()
-    })?;
+    }
+}
 
-    let tab = browser.new_tab()?;
+/// Open the row's page in `tab` and read its value.
+fn load_page_value(
+    row: &crate::app::ValueData,
+    tab: &headless_chrome::Tab,
+    settings: &browser::BrowserSettings,
+) -> Result<FetchResult, Box<dyn Error>> {
+    let url = &row.link;
     tab.disable_debugger()?;
+    if !row.session.cookies.is_empty() {
+        session::set_cookies(tab, &row.session.cookies)?;
+    }
 
     // Navigate to the URL
     tab.navigate_to(url)?;
+    browser::wait_for_page(tab, settings)?;
+    let logged_in = session::ensure_logged_in(tab, url, &row.session)?;
+    if logged_in {
+        browser::wait_for_page(tab, settings)?;
+    }
+    steps::run_steps(tab, &row.steps)?;
+
+    let mut result = read_value(row, tab, settings)?;
+    if !result.value.is_empty() && !row.fields.is_empty() {
+        result.fields = fields::read_fields(tab, &result.value, &row.fields);
+    }
+    // keep the jar up to date with what the site refreshed or handed out at login
+    if logged_in || !row.session.cookies.is_empty() {
+        result.cookies = Some(session::read_cookies(tab)?);
+    }
+    Ok(result)
+}
+
+/// Read the row's value from the page `tab` is on.
+fn read_value(
+    row: &crate::app::ValueData,
+    tab: &headless_chrome::Tab,
+    settings: &browser::BrowserSettings,
+) -> Result<FetchResult, Box<dyn Error>> {
+    if !row.script.trim().is_empty() {
+        let value = script::run_extractor(tab, &row.script)?;
+        return Ok(FetchResult {
+            value: preset::apply_transforms(&row.transforms, value),
+            ..Default::default()
+        });
+    }
+
+    if let RowMode::Availability(rules) = &row.mode {
+        let available = availability::is_available(tab, rules)?;
+        return Ok(FetchResult {
+            value: availability::status_text(available).to_owned(),
+            ..Default::default()
+        });
+    }
+
+    let selectors = row.selectors();
+
+    // wait until any of the selectors matches
+    if let Err(e) = tab.wait_for_element(&selectors.join(", ")) {
+        // the page may have been redesigned, look for the element we saw last time
+        let Some(fingerprint) = &row.fingerprint else {
+            return Err(e.into());
+        };
+        let suggestion = heal::suggest_selector(tab, fingerprint)?;
+        if let Some(suggestion) = &suggestion {
+            println!(
+                "Selector {:?} no longer matches, suggesting {:?}",
+                row.css_selector, suggestion.selector
+            );
+        }
+        return Ok(FetchResult {
+            suggestion,
+            error: Some(format!(
+                "no element matches {:?}: {}",
+                selectors.join(", "),
+                e
+            )),
+            ..Default::default()
+        });
+    }
+
+    // the element may show a placeholder until scripts fill the value in
+    let wait_for_text = settings.wait_for_text.compile()?;
+    let element_timeout = Duration::from_secs(settings.element_timeout_secs.max(1));
+    let started = Instant::now();
+    loop {
+        for (index, css_selector) in selectors.iter().enumerate() {
+            let value_string = match row.mode {
+                RowMode::Text { html } => read_region(tab, css_selector, html)?,
+                _ => read_text(tab, css_selector)?,
+            };
+            if value_string.is_empty() || !wait_for_text.is_met(&value_string) {
+                continue;
+            }
+            if index > 0 {
+                println!(
+                    "Primary selector of {:?} found nothing, used fallback {:?}",
+                    row.name, css_selector
+                );
+            }
+            return Ok(FetchResult {
+                value: preset::apply_transforms(&row.transforms, value_string),
+                // the value was read, so a failed fingerprint only costs healing later
+                fingerprint: heal::capture(tab, css_selector).unwrap_or_else(|e| {
+                    println!("Couldn't fingerprint {:?}: {}", css_selector, e);
+                    None
+                }),
+                matched_selector: Some(index),
+                ..Default::default()
+            });
+        }
+        if let browser::TextCheck::Present = wait_for_text {
+            return Ok(FetchResult::default());
+        }
+        if started.elapsed() >= element_timeout {
+            return Err(format!(
+                "the text didn't meet \"{}\" within {} s",
+                settings.wait_for_text.as_str(),
+                element_timeout.as_secs()
+            )
+            .into());
+        }
+        std::thread::sleep(TEXT_POLL_INTERVAL);
+    }
+}
 
-    let _e = tab.wait_for_element(css_selector)?;
+/// Text of the first element matching `css_selector` as shown, keeping its
+/// line breaks, or its HTML.
+fn read_region(
+    tab: &headless_chrome::Tab,
+    css_selector: &str,
+    html: bool,
+) -> Result<String, Box<dyn Error>> {
+    let property = if html { "outerHTML" } else { "innerText" };
+    let result = tab.evaluate(
+        &format!("document.querySelector({:?})?.{}", css_selector, property),
+        false,
+    )?;
+    Ok(result
+        .value
+        .as_ref()
+        .and_then(|value| value.as_str())
+        .unwrap_or_default()
+        .trim()
+        .to_owned())
+}
 
+/// Trimmed text of the first element matching `css_selector`, empty if there is none.
+fn read_text(tab: &headless_chrome::Tab, css_selector: &str) -> Result<String, Box<dyn Error>> {
     let value_js_result = tab.evaluate(
         &format!(
             r#"
@@ -31,7 +274,10 @@ async fn get_current_value(url: &str, css_selector: &str) -> Result<String, Box<
     )?;
 
     // Extract the value string from the JavaScript result
-    let mut value_string = value_js_result.value.unwrap().to_string();
+    let Some(value) = value_js_result.value else {
+        return Ok(String::new());
+    };
+    let mut value_string = value.to_string();
     println!("value_string: {}", value_string);
 
     // Remove leading and trailing quotes
@@ -39,61 +285,58 @@ async fn get_current_value(url: &str, css_selector: &str) -> Result<String, Box<
         value_string.remove(0);
         value_string.pop();
     }
-    value_string = value_string.trim().to_string();
-
-    Ok(value_string)
+    Ok(value_string.trim().to_string())
 }
 
-fn get_web_value_promise(
-    id: String,
-    link: String,
-    css_selector: String,
-) -> Promise<(String, String)> {
+fn get_web_value_promise(row: crate::app::ValueData) -> Promise<(String, FetchResult)> {
     let runtime = tokio::runtime::Runtime::new().unwrap();
     Promise::spawn_blocking(move || {
-        let result = runtime.block_on(async {
-            crate::get_current_value(&link, &css_selector)
-                .await
-                .unwrap_or_default()
-        });
-        (id, result)
+        let result = runtime.block_on(crate::get_current_value(&row));
+        (row.id, result)
     })
 }
 
-fn show_notifcation(name: &String, old_value: &String, new_value: &String) {
+fn show_notifcation(body: &str) {
     Notification::new()
         .summary("Web value tracker")
-        .body(
-            format!(
-                "Value of: {:?} changed from: {:?} to: {:?}\nAt time: {:?}",
-                name,
-                old_value,
-                new_value,
-                crate::get_current_date_time(),
-            )
-            .as_str(),
-        )
+        .body(body)
         .timeout(Timeout::Never) // this however is
         .show()
         .unwrap();
 }
 
 fn get_current_date_time() -> String {
-    chrono::Local::now().format("%b %d %H:%M:%S %Y").to_string()
+    format_date_time(&chrono::Local::now())
+}
+
+fn format_date_time<Tz: chrono::TimeZone>(date_time: &chrono::DateTime<Tz>) -> String {
+    date_time
+        .with_timezone(&chrono::Local)
+        .format("%b %d %H:%M:%S %Y")
+        .to_string()
+}
+
+/// Extract the first number from a scraped value, e.g. "$1,299.99" -> 1299.99.
+fn parse_number(value: &str) -> Option<f64> {
+    static NUMBER_REGEX: OnceLock<Regex> = OnceLock::new();
+    let regex = NUMBER_REGEX.get_or_init(|| Regex::new(r"-?\d[\d,]*(\.\d+)?").unwrap());
+    regex
+        .find(value)
+        .and_then(|m| m.as_str().replace(',', "").parse().ok())
 }
 
 fn fetch_latest_values_promises(
-    table_data: &Vec<crate::app::ValueData>,
-) -> VecDeque<Promise<(String, String)>> {
+    table_data: &[crate::app::ValueData],
+) -> VecDeque<Promise<(String, FetchResult)>> {
     println!("fetching latest values");
     let mut promises = VecDeque::new();
 
-    for row in table_data {
-        let id = row.id.clone();
-        let link = row.link.clone();
-        let css_selector = row.css_selector.clone();
-
-        let promise = get_web_value_promise(id, link, css_selector);
+    let now = chrono::Utc::now();
+    for row in table_data
+        .iter()
+        .filter(|row| !row.is_paused(now) && !row.is_computed())
+    {
+        let promise = get_web_value_promise(row.clone());
         promises.push_back(promise);
     }
 
@@ -101,51 +344,463 @@ fn fetch_latest_values_promises(
 }
 
 fn fetch_latest_values_and_notify_blocking(
-    table_data: &mut Vec<crate::app::ValueData>,
-) -> VecDeque<(String, String)> {
+    table_data: &crate::app::SharedTableData,
+    notifier: &Mutex<Notifier>,
+    should_fetch: impl Fn(&crate::app::ValueData) -> bool,
+) {
     println!("fetching latest values, notify");
     let mut new_values = VecDeque::new();
+    let mut alerts = Vec::new();
 
-    for row in &*table_data {
+    // Fetch from a copy so the UI isn't blocked while pages load.
+    let rows = table_data.lock().unwrap().clone();
+    let now = chrono::Utc::now();
+    for row in rows
+        .iter()
+        .filter(|row| !row.is_paused(now) && !row.is_computed() && should_fetch(row))
+    {
         let id = row.id.clone();
         let name = row.name.clone();
         let link = row.link.clone();
-        let css_selector = row.css_selector.clone();
         let old_value = row.latest_value.clone();
 
-        let new_value = get_web_value_blocking(link, css_selector);
+        let result = get_web_value_blocking(row);
+        let new_value = result.value.clone();
 
         if !new_value.is_empty() && new_value != old_value {
-            show_notifcation(&name, &old_value, &new_value);
+            let alert = Alert {
+                id: id.clone(),
+                name,
+                old_value,
+                new_value: new_value.clone(),
+                changed_at: chrono::Utc::now(),
+                mode: row.mode.clone(),
+                screenshot: result.screenshot.clone(),
+            };
+            if !row.on_change_command.is_empty() {
+                hook::run_on_change_command(&row.on_change_command, &link, &alert);
+            }
+            alerts.push(alert);
+        }
+        new_values.push_back((id, result));
+    }
+    let computed: Vec<(Alert, String)> = {
+        let mut table_data = table_data.lock().unwrap();
+        update_backend_table_values(&mut table_data, new_values)
+            .into_iter()
+            .map(|alert| {
+                let command = table_data
+                    .iter()
+                    .find(|row| row.id == alert.id)
+                    .map(|row| row.on_change_command.clone())
+                    .unwrap_or_default();
+                (alert, command)
+            })
+            .collect()
+    };
+    for (alert, command) in computed {
+        if !command.is_empty() {
+            hook::run_on_change_command(&command, "", &alert);
         }
-        new_values.push_back((id, new_value));
+        alerts.push(alert);
     }
-    //update this backend table's values as well
-    update_backend_table_values(table_data, new_values.clone());
-    new_values
+    // taken only now, so flushing deferred alerts and digests don't wait for page loads
+    notifier
+        .lock()
+        .unwrap()
+        .notify(alerts, chrono::Local::now());
 }
 
-fn get_web_value_blocking(link: String, css_selector: String) -> String {
+fn get_web_value_blocking(row: &crate::app::ValueData) -> FetchResult {
     tokio::task::block_in_place(|| {
-        tokio::runtime::Handle::current().block_on(async {
-            crate::get_current_value(&link, &css_selector)
-                .await
-                .unwrap_or_default()
-        })
+        tokio::runtime::Handle::current().block_on(crate::get_current_value(row))
     })
 }
 
+/// Apply fetched values, then recompute the computed rows. Returns alerts for
+/// the computed rows that changed.
 fn update_backend_table_values(
-    table_data: &mut Vec<crate::app::ValueData>,
-    new_values: VecDeque<(String, String)>,
-) {
-    for (id, value) in new_values {
-        println!("Backend: Updating value for ID: {}, Value: {}", id, value);
+    table_data: &mut [crate::app::ValueData],
+    new_values: VecDeque<(String, FetchResult)>,
+) -> Vec<Alert> {
+    for (id, result) in new_values {
+        println!(
+            "Backend: Updating value for ID: {}, Value: {}",
+            id, result.value
+        );
         if let Some(index) = table_data.iter().position(|row| row.id == id) {
-            let row = &mut table_data[index];
-            row.previous_value = row.latest_value.clone();
-            row.latest_value = value;
-            row.last_updated = crate::get_current_date_time();
+            apply_new_value(&mut table_data[index], result);
+        }
+    }
+    update_computed_rows(table_data)
+}
+
+/// What computed rows can refer to: each row's latest value by name, and
+/// its fields as "name.field". The first of several rows with a name wins.
+fn formula_values(table_data: &[crate::app::ValueData]) -> HashMap<String, String> {
+    let mut values = HashMap::new();
+    for row in table_data {
+        values
+            .entry(row.name.clone())
+            .or_insert_with(|| row.latest_value.clone());
+        for field in &row.fields {
+            values
+                .entry(format!("{}.{}", row.name, field.name))
+                .or_insert_with(|| field.value.clone());
+        }
+    }
+    values
+}
+
+fn evaluate_formula(
+    formula: &str,
+    own_name: &str,
+    values: &HashMap<String, String>,
+) -> Result<String, String> {
+    let resolve = |name: &str| {
+        if name == own_name {
+            return Err(format!("{:?} refers to itself", name));
+        }
+        let value = values
+            .get(name)
+            .ok_or_else(|| format!("no row named {:?}", name))?;
+        parse_number(value).ok_or_else(|| format!("{:?} has no number", name))
+    };
+    expr::evaluate(formula, &resolve).map(expr::format_number)
+}
+
+/// Value of the computed `row` from the latest values of `table_data`.
+fn compute_value(table_data: &[crate::app::ValueData], row: &crate::app::ValueData) -> FetchResult {
+    let RowMode::Computed { formula } = &row.mode else {
+        return FetchResult::default();
+    };
+    match evaluate_formula(formula, &row.name, &formula_values(table_data)) {
+        Ok(value) => FetchResult {
+            value,
+            ..Default::default()
+        },
+        Err(error) => FetchResult {
+            error: Some(error),
+            ..Default::default()
+        },
+    }
+}
+
+/// Index of the first row with each name, the one formulas refer to.
+fn first_with_name(table_data: &[crate::app::ValueData]) -> HashMap<&str, usize> {
+    let mut first = HashMap::new();
+    for (index, row) in table_data.iter().enumerate() {
+        first.entry(row.name.as_str()).or_insert(index);
+    }
+    first
+}
+
+/// Index of the row a name in a formula refers to, either by its own name
+/// or as "name.field".
+fn referenced_row(name: &str, first_with_name: &HashMap<&str, usize>) -> Option<usize> {
+    first_with_name.get(name).copied().or_else(|| {
+        name.match_indices('.')
+            .find_map(|(dot, _)| first_with_name.get(&name[..dot]).copied())
+    })
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Visit {
+    New,
+    Visiting,
+    Done,
+}
+
+/// Depth first walk over the computed rows, listing each after the rows it
+/// depends on.
+struct EvaluationOrder<'a> {
+    dependencies: &'a [Vec<usize>],
+    visits: Vec<Visit>,
+    stack: Vec<usize>,
+    in_cycle: Vec<bool>,
+    order: Vec<usize>,
+}
+
+impl EvaluationOrder<'_> {
+    fn visit(&mut self, index: usize) {
+        self.visits[index] = Visit::Visiting;
+        self.stack.push(index);
+        for &dependency in &self.dependencies[index] {
+            match self.visits[dependency] {
+                Visit::New => self.visit(dependency),
+                Visit::Visiting => {
+                    let start = self.stack.iter().position(|&i| i == dependency).unwrap();
+                    for &i in &self.stack[start..] {
+                        self.in_cycle[i] = true;
+                    }
+                }
+                Visit::Done => {}
+            }
+        }
+        self.stack.pop();
+        self.visits[index] = Visit::Done;
+        self.order.push(index);
+    }
+}
+
+/// Indices of the computed rows that aren't paused, each after the computed
+/// rows it refers to, and whether it's on a cycle of them.
+fn computed_order(
+    table_data: &[crate::app::ValueData],
+    now: chrono::DateTime<chrono::Utc>,
+) -> Vec<(usize, bool)> {
+    let first_with_name = first_with_name(table_data);
+    let active = |row: &crate::app::ValueData| row.is_computed() && !row.is_paused(now);
+    let dependencies: Vec<Vec<usize>> = table_data
+        .iter()
+        .enumerate()
+        .map(|(index, row)| match &row.mode {
+            RowMode::Computed { formula } if active(row) => expr::names(formula)
+                .iter()
+                .filter_map(|name| referenced_row(name, &first_with_name))
+                // referring to itself is reported by the evaluation
+                .filter(|&dependency| dependency != index && active(&table_data[dependency]))
+                .collect(),
+            _ => Vec::new(),
+        })
+        .collect();
+
+    let mut walk = EvaluationOrder {
+        dependencies: &dependencies,
+        visits: vec![Visit::New; table_data.len()],
+        stack: Vec::new(),
+        in_cycle: vec![false; table_data.len()],
+        order: Vec::new(),
+    };
+    for (index, row) in table_data.iter().enumerate() {
+        if active(row) && walk.visits[index] == Visit::New {
+            walk.visit(index);
+        }
+    }
+    walk.order
+        .iter()
+        .map(|&index| (index, walk.in_cycle[index]))
+        .collect()
+}
+
+/// Recompute the computed rows from the rows they refer to, each after the
+/// computed rows it uses. Returns alerts for those whose value changed.
+fn update_computed_rows(table_data: &mut [crate::app::ValueData]) -> Vec<Alert> {
+    let now = chrono::Utc::now();
+    let mut values = formula_values(table_data);
+    let first_with_name = first_with_name(table_data);
+    let mut results = HashMap::new();
+    for (index, in_cycle) in computed_order(table_data, now) {
+        let row = &table_data[index];
+        let RowMode::Computed { formula } = &row.mode else {
+            continue;
+        };
+        let result = if in_cycle {
+            Err(format!(
+                "{:?} depends on itself through other computed rows",
+                row.name
+            ))
+        } else {
+            evaluate_formula(formula, &row.name, &values)
+        };
+        // later rows with the same name aren't what formulas refer to
+        if first_with_name.get(row.name.as_str()) == Some(&index) {
+            values.insert(row.name.clone(), result.clone().unwrap_or_default());
+        }
+        results.insert(row.id.clone(), result);
+    }
+
+    let mut alerts = Vec::new();
+    for row in table_data.iter_mut() {
+        let Some(result) = results.remove(&row.id) else {
+            continue;
+        };
+        let (value, error) = match result {
+            Ok(value) => (value, None),
+            Err(error) => (String::new(), Some(error)),
+        };
+        if value == row.latest_value && error == row.last_error {
+            continue;
+        }
+        if !value.is_empty() && value != row.latest_value {
+            alerts.push(Alert {
+                id: row.id.clone(),
+                name: row.name.clone(),
+                old_value: row.latest_value.clone(),
+                new_value: value.clone(),
+                changed_at: now,
+                mode: row.mode.clone(),
+                screenshot: None,
+            });
         }
+        apply_new_value(
+            row,
+            FetchResult {
+                value,
+                error,
+                ..Default::default()
+            },
+        );
+    }
+    alerts
+}
+
+fn apply_new_value(row: &mut crate::app::ValueData, result: FetchResult) {
+    let value = result.value;
+    if result.fingerprint.is_some() {
+        row.fingerprint = result.fingerprint;
+        row.selector_suggestion = None;
+    }
+    if result.suggestion.is_some() {
+        row.selector_suggestion = result.suggestion;
+    }
+    if result.matched_selector.is_some() {
+        row.matched_selector = result.matched_selector;
+    }
+    if let Some(cookies) = result.cookies {
+        session::merge_cookies(&mut row.session.cookies, cookies);
+    }
+    row.last_error = result.error;
+    for (index, field) in row.fields.iter_mut().enumerate() {
+        field.value = result.fields.get(index).cloned().unwrap_or_default();
+    }
+    metrics::record_fetch(&row.id, &value);
+    if let Some(number) = parse_number(&value).filter(|_| row.mode.is_numeric()) {
+        row.lowest_value = Some(row.lowest_value.map_or(number, |lowest| lowest.min(number)));
+        row.highest_value = Some(
+            row.highest_value
+                .map_or(number, |highest| highest.max(number)),
+        );
+    }
+    let cur_date_time = chrono::Utc::now();
+    if !value.is_empty() && value != row.latest_value {
+        row.history.push(crate::app::HistoryEntry {
+            value: value.clone(),
+            timestamp: cur_date_time,
+            screenshot: result.screenshot.clone(),
+            fields: row
+                .fields
+                .iter()
+                .map(|field| (field.name.clone(), field.value.clone()))
+                .collect(),
+        });
+        let max_entries = match row.mode {
+            RowMode::Text { .. } => MAX_TEXT_HISTORY_ENTRIES,
+            _ => MAX_HISTORY_ENTRIES,
+        };
+        if row.history.len() > max_entries {
+            let excess = row.history.len() - max_entries;
+            for dropped in row.history.drain(..excess) {
+                if let Some(path) = dropped.screenshot {
+                    screenshot::remove(&path);
+                }
+            }
+        }
+    }
+    // only the latest failure is kept, and none once the row works again
+    let failure_screenshot = if value.is_empty() {
+        result.screenshot
+    } else {
+        None
+    };
+    if let Some(path) = std::mem::replace(&mut row.failure_screenshot, failure_screenshot) {
+        screenshot::remove(&path);
+    }
+    let old_value = std::mem::replace(&mut row.latest_value, value);
+    row.previous_value = old_value.clone();
+    row.last_updated = cur_date_time;
+    mqtt::publish_update(row, &old_value);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::ValueData;
+
+    fn fetched(value: &str) -> FetchResult {
+        FetchResult {
+            value: value.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn text_rows_keep_a_shorter_history() {
+        let mut row = ValueData::new(
+            "Changelog".to_owned(),
+            "https://example.com".to_owned(),
+            "main".to_owned(),
+            String::new(),
+        );
+        row.mode = RowMode::Text { html: false };
+        for version in 0..MAX_TEXT_HISTORY_ENTRIES + 10 {
+            apply_new_value(&mut row, fetched(&format!("version {}", version)));
+        }
+        assert_eq!(row.history.len(), MAX_TEXT_HISTORY_ENTRIES);
+        assert_eq!(row.history[0].value, "version 10");
+    }
+
+    fn row(name: &str, value: &str) -> ValueData {
+        ValueData::new(
+            name.to_owned(),
+            "https://example.com".to_owned(),
+            ".price".to_owned(),
+            value.to_owned(),
+        )
+    }
+
+    fn computed(name: &str, formula: &str) -> ValueData {
+        let mut row = row(name, "");
+        row.mode = RowMode::Computed {
+            formula: formula.to_owned(),
+        };
+        row
+    }
+
+    #[test]
+    fn computed_rows_follow_the_rows_they_use() {
+        // listed before the row it uses, so it must still be evaluated after it
+        let mut table_data = vec![
+            computed("Double", "Total * 2"),
+            computed("Total", "Price + 1"),
+            row("Price", "10"),
+        ];
+        let alerts = update_computed_rows(&mut table_data);
+        assert_eq!(table_data[0].latest_value, "22");
+        assert_eq!(table_data[1].latest_value, "11");
+        assert_eq!(alerts.len(), 2);
+    }
+
+    #[test]
+    fn cycles_are_reported_not_repeated() {
+        let mut table_data = vec![
+            computed("A", "B + 1"),
+            computed("B", "A + 1"),
+            computed("C", "A * 2"),
+        ];
+        for _ in 0..3 {
+            assert!(update_computed_rows(&mut table_data).is_empty());
+        }
+        for row in &table_data[..2] {
+            assert!(row.latest_value.is_empty());
+            assert!(row
+                .last_error
+                .as_ref()
+                .unwrap()
+                .contains("depends on itself"));
+        }
+        assert!(table_data[2].last_error.is_some());
+    }
+
+    #[test]
+    fn the_first_row_with_a_name_wins() {
+        let mut table_data = vec![
+            row("Price", "5"),
+            computed("Price", "100"),
+            computed("Total", "Price + 1"),
+        ];
+        update_computed_rows(&mut table_data);
+        assert_eq!(table_data[1].latest_value, "100");
+        assert_eq!(table_data[2].latest_value, "6");
     }
 }
@@ -19,7 +19,7 @@ async fn main() -> eframe::Result {
         ..Default::default()
     };
     eframe::run_native(
-        "Web value tracker",
+        web_value_tracker::APP_NAME,
         native_options,
         Box::new(|cc| Ok(Box::new(web_value_tracker::ThisApp::new(cc)))),
     )
@@ -0,0 +1,250 @@
+use std::{
+    collections::HashMap,
+    fmt::Write,
+    sync::{Mutex, MutexGuard, OnceLock},
+    thread,
+    time::Duration,
+};
+use tiny_http::{Header, Response, Server};
+
+use crate::app::{SharedTableData, ValueData};
+
+const DEFAULT_METRICS_PORT: u16 = 9898;
+const FETCH_DURATION_BUCKETS: [f64; 8] = [0.5, 1.0, 2.5, 5.0, 10.0, 20.0, 30.0, 60.0];
+
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
+#[serde(default)]
+pub struct MetricsSettings {
+    pub enabled: bool,
+    pub port: u16,
+}
+
+impl Default for MetricsSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: DEFAULT_METRICS_PORT,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Metrics {
+    fetch_errors: HashMap<String, u64>,
+    last_success: HashMap<String, i64>,
+    fetch_duration_buckets: [u64; FETCH_DURATION_BUCKETS.len()],
+    fetch_duration_sum: f64,
+    fetch_duration_count: u64,
+}
+
+fn metrics() -> MutexGuard<'static, Metrics> {
+    static METRICS: OnceLock<Mutex<Metrics>> = OnceLock::new();
+    METRICS.get_or_init(Default::default).lock().unwrap()
+}
+
+pub fn record_fetch_duration(duration: Duration) {
+    let seconds = duration.as_secs_f64();
+    let mut metrics = metrics();
+    for (bucket, upper_bound) in FETCH_DURATION_BUCKETS.iter().enumerate() {
+        if seconds <= *upper_bound {
+            metrics.fetch_duration_buckets[bucket] += 1;
+        }
+    }
+    metrics.fetch_duration_sum += seconds;
+    metrics.fetch_duration_count += 1;
+}
+
+/// Count a fetch result for a row; an empty value means the fetch failed.
+pub fn record_fetch(id: &str, value: &str) {
+    let mut metrics = metrics();
+    if value.is_empty() {
+        *metrics.fetch_errors.entry(id.to_owned()).or_default() += 1;
+    } else {
+        metrics
+            .last_success
+            .insert(id.to_owned(), chrono::Utc::now().timestamp());
+    }
+}
+
+/// Render all metrics in the Prometheus text exposition format.
+/// Per-row series are only written for rows that still exist.
+pub fn render(table_data: &[ValueData]) -> String {
+    let metrics = metrics();
+    let mut out = String::new();
+
+    out.push_str(
+        "# HELP web_value Latest tracked value, for value and computed rows holding a number.\n",
+    );
+    out.push_str("# TYPE web_value gauge\n");
+    // text and availability rows may contain numbers, but aren't one
+    for row in table_data.iter().filter(|row| row.mode.is_numeric()) {
+        if let Some(value) = crate::parse_number(&row.latest_value) {
+            writeln!(out, "web_value{{{}}} {}", row_labels(row), value).unwrap();
+        }
+    }
+
+    out.push_str("# HELP web_value_fetch_errors_total Fetches that returned no value.\n");
+    out.push_str("# TYPE web_value_fetch_errors_total counter\n");
+    for row in table_data {
+        let errors = metrics.fetch_errors.get(&row.id).copied().unwrap_or(0);
+        writeln!(
+            out,
+            "web_value_fetch_errors_total{{{}}} {}",
+            row_labels(row),
+            errors
+        )
+        .unwrap();
+    }
+
+    out.push_str(
+        "# HELP web_value_last_success_timestamp_seconds Unix time of the last successful fetch.\n",
+    );
+    out.push_str("# TYPE web_value_last_success_timestamp_seconds gauge\n");
+    for row in table_data {
+        if let Some(timestamp) = metrics.last_success.get(&row.id) {
+            writeln!(
+                out,
+                "web_value_last_success_timestamp_seconds{{{}}} {}",
+                row_labels(row),
+                timestamp
+            )
+            .unwrap();
+        }
+    }
+
+    out.push_str(
+        "# HELP web_value_fetch_duration_seconds Time taken to load a page and read its value.\n",
+    );
+    out.push_str("# TYPE web_value_fetch_duration_seconds histogram\n");
+    for (bucket, upper_bound) in FETCH_DURATION_BUCKETS.iter().enumerate() {
+        writeln!(
+            out,
+            "web_value_fetch_duration_seconds_bucket{{le=\"{}\"}} {}",
+            upper_bound, metrics.fetch_duration_buckets[bucket]
+        )
+        .unwrap();
+    }
+    writeln!(
+        out,
+        "web_value_fetch_duration_seconds_bucket{{le=\"+Inf\"}} {}",
+        metrics.fetch_duration_count
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "web_value_fetch_duration_seconds_sum {}",
+        metrics.fetch_duration_sum
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "web_value_fetch_duration_seconds_count {}",
+        metrics.fetch_duration_count
+    )
+    .unwrap();
+
+    out
+}
+
+fn row_labels(row: &ValueData) -> String {
+    format!(
+        "id=\"{}\",name=\"{}\"",
+        escape_label(&row.id),
+        escape_label(&row.name)
+    )
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Serve `GET /metrics` on localhost for Prometheus to scrape.
+pub fn start_metrics_server(
+    settings: MetricsSettings,
+    table_data: SharedTableData,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let server = match Server::http(("127.0.0.1", settings.port)) {
+            Ok(server) => server,
+            Err(e) => {
+                eprintln!("Failed to start metrics server: {:?}", e);
+                return;
+            }
+        };
+        println!(
+            "Metrics available on http://127.0.0.1:{}/metrics",
+            settings.port
+        );
+
+        for request in server.incoming_requests() {
+            let response = if request.url() == "/metrics" {
+                let body = render(&table_data.lock().unwrap());
+                Response::from_string(body).with_header(
+                    Header::from_bytes("Content-Type", "text/plain; version=0.0.4").unwrap(),
+                )
+            } else {
+                Response::from_string("not found").with_status_code(404)
+            };
+            if let Err(e) = request.respond(response) {
+                eprintln!("Failed to send metrics response: {:?}", e);
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::RowMode;
+
+    fn row(name: &str, value: &str, mode: RowMode) -> ValueData {
+        let mut row = ValueData::new(
+            name.to_owned(),
+            "https://example.com".to_owned(),
+            ".price".to_owned(),
+            value.to_owned(),
+        );
+        row.mode = mode;
+        row
+    }
+
+    fn value_lines(out: &str) -> Vec<&str> {
+        out.lines()
+            .filter(|line| line.starts_with("web_value{"))
+            .collect()
+    }
+
+    #[test]
+    fn only_numeric_rows_export_a_value() {
+        let table_data = [
+            row("Book", "$1,299.50", RowMode::Value),
+            row("Terms", "Version 2 of 2024", RowMode::Text { html: false }),
+            row("Console", "In stock", RowMode::Availability(Vec::new())),
+            row(
+                "Total",
+                "12",
+                RowMode::Computed {
+                    formula: "Book + 1".to_owned(),
+                },
+            ),
+            row("Broken", "", RowMode::Value),
+        ];
+        let out = render(&table_data);
+        let lines = value_lines(&out);
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].contains("name=\"Book\""));
+        assert!(lines[0].ends_with(" 1299.5"));
+        assert!(lines[1].contains("name=\"Total\""));
+        assert!(lines[1].ends_with(" 12"));
+    }
+
+    #[test]
+    fn labels_are_escaped() {
+        let table_data = [row("A \"quoted\"\\name\nhere", "3", RowMode::Value)];
+        let out = render(&table_data);
+        assert!(value_lines(&out)[0].contains("name=\"A \\\"quoted\\\"\\\\name\\nhere\""));
+    }
+}
@@ -0,0 +1,300 @@
+use rumqttc::{Client, MqttOptions, QoS};
+use serde_json::json;
+use std::{
+    collections::HashMap,
+    sync::{Mutex, OnceLock},
+    thread,
+    time::Duration,
+};
+
+use crate::app::{RowMode, ValueData};
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+/// Home Assistant rejects longer states.
+const MAX_STATE_CHARS: usize = 255;
+
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
+#[serde(default)]
+pub struct MqttSettings {
+    pub enabled: bool,
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+    pub password: String,
+    pub client_id: String,
+    pub topic_prefix: String,
+    /// Announce every row as a Home Assistant sensor.
+    pub discovery: bool,
+    pub discovery_prefix: String,
+    pub qos: u8,
+}
+
+impl Default for MqttSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: "localhost".to_owned(),
+            port: 1883,
+            username: String::new(),
+            password: String::new(),
+            client_id: "web_value_tracker".to_owned(),
+            topic_prefix: "web_value_tracker".to_owned(),
+            discovery: true,
+            discovery_prefix: "homeassistant".to_owned(),
+            qos: 1,
+        }
+    }
+}
+
+struct Publisher {
+    client: Client,
+    settings: MqttSettings,
+    qos: QoS,
+    /// Row id to the name its discovery config was sent with.
+    announced: Mutex<HashMap<String, String>>,
+}
+
+static PUBLISHER: OnceLock<Publisher> = OnceLock::new();
+
+/// Connect to the broker and publish the current value of every row.
+/// Later updates are published by `publish_update` as values come in.
+pub fn start_mqtt_publisher(settings: MqttSettings, table_data: &[ValueData]) {
+    let mut options = MqttOptions::new(&settings.client_id, &settings.host, settings.port);
+    options.set_keep_alive(Duration::from_secs(30));
+    if !settings.username.is_empty() {
+        options.set_credentials(&settings.username, &settings.password);
+    }
+    let qos = rumqttc::qos(settings.qos).unwrap_or(QoS::AtLeastOnce);
+    let (client, mut connection) = Client::new(options, 100);
+
+    // the connection has to be polled for anything to be sent
+    thread::spawn(move || {
+        for event in connection.iter() {
+            if let Err(e) = event {
+                eprintln!("MQTT connection error: {:?}", e);
+                thread::sleep(RECONNECT_DELAY);
+            }
+        }
+    });
+
+    let publisher = Publisher {
+        client,
+        settings,
+        qos,
+        announced: Mutex::new(HashMap::new()),
+    };
+    if PUBLISHER.set(publisher).is_err() {
+        return;
+    }
+    for row in table_data {
+        publish_update(row, &row.latest_value);
+    }
+}
+
+/// Publish a row's latest value, and a change event if it differs from `old_value`.
+pub fn publish_update(row: &ValueData, old_value: &str) {
+    let Some(publisher) = PUBLISHER.get() else {
+        return;
+    };
+    publisher.announce(row);
+    if row.latest_value.is_empty() {
+        return;
+    }
+
+    publisher.publish(
+        &publisher.state_topic(&row.id),
+        true,
+        state_payload(row).to_string(),
+    );
+
+    if row.latest_value != old_value {
+        let change = json!({
+            "name": row.name,
+            "link": row.link,
+            "old_value": old_value,
+            "new_value": row.latest_value,
+            "changed_at": row.last_updated,
+        });
+        publisher.publish(
+            &format!("{}/{}/change", publisher.settings.topic_prefix, row.id),
+            false,
+            change.to_string(),
+        );
+    }
+}
+
+/// Clear the retained state and discovery config of a deleted row.
+pub fn remove_row(id: &str) {
+    let Some(publisher) = PUBLISHER.get() else {
+        return;
+    };
+    publisher.publish(&publisher.state_topic(id), true, String::new());
+    if publisher.settings.discovery {
+        publisher.publish(&publisher.discovery_topic(id), true, String::new());
+    }
+    publisher.announced.lock().unwrap().remove(id);
+}
+
+/// The retained state of a row. Its `value` is the Home Assistant state, the
+/// other keys are attributes.
+fn state_payload(row: &ValueData) -> serde_json::Value {
+    let mut state = json!({
+        "value": state_value(row),
+        "number": crate::parse_number(&row.latest_value).filter(|_| row.mode.is_numeric()),
+        "last_updated": row.last_updated,
+        "link": row.link,
+        "fields": row
+            .fields
+            .iter()
+            .map(|field| (field.name.clone(), json!(field.value)))
+            .collect::<serde_json::Map<_, _>>(),
+    });
+    if let RowMode::Text { .. } = row.mode {
+        state["text"] = json!(row.latest_value);
+    }
+    state
+}
+
+/// What Home Assistant shows: the value, or for text rows a summary of
+/// their last change, as the whole text would be too long.
+fn state_value(row: &ValueData) -> String {
+    let value = match row.mode {
+        RowMode::Text { .. } => {
+            // the last entry is the latest text
+            let previous = row
+                .history
+                .iter()
+                .rev()
+                .nth(1)
+                .map_or("", |entry| entry.value.as_str());
+            crate::diff::summary(previous, &row.latest_value)
+        }
+        _ => row.latest_value.clone(),
+    };
+    value.chars().take(MAX_STATE_CHARS).collect()
+}
+
+fn state_topic(settings: &MqttSettings, id: &str) -> String {
+    format!("{}/{}/state", settings.topic_prefix, id)
+}
+
+fn discovery_topic(settings: &MqttSettings, id: &str) -> String {
+    format!(
+        "{}/sensor/web_value_tracker_{}/config",
+        settings.discovery_prefix, id
+    )
+}
+
+/// Home Assistant discovery config announcing `row` as a sensor.
+fn discovery_config(settings: &MqttSettings, row: &ValueData) -> serde_json::Value {
+    let state_topic = state_topic(settings, &row.id);
+    json!({
+        "name": row.name,
+        "unique_id": format!("web_value_tracker_{}", row.id),
+        "state_topic": state_topic,
+        "value_template": "{{ value_json.value }}",
+        "json_attributes_topic": state_topic,
+        "icon": "mdi:web",
+        "device": {
+            "identifiers": ["web_value_tracker"],
+            "name": "Web value tracker",
+        },
+    })
+}
+
+impl Publisher {
+    fn state_topic(&self, id: &str) -> String {
+        state_topic(&self.settings, id)
+    }
+
+    fn discovery_topic(&self, id: &str) -> String {
+        discovery_topic(&self.settings, id)
+    }
+
+    /// Send the Home Assistant discovery config, once per row and name.
+    fn announce(&self, row: &ValueData) {
+        if !self.settings.discovery {
+            return;
+        }
+        let mut announced = self.announced.lock().unwrap();
+        if announced.get(&row.id) == Some(&row.name) {
+            return;
+        }
+        announced.insert(row.id.clone(), row.name.clone());
+
+        let config = discovery_config(&self.settings, row);
+        self.publish(&self.discovery_topic(&row.id), true, config.to_string());
+    }
+
+    fn publish(&self, topic: &str, retain: bool, payload: String) {
+        if let Err(e) = self.client.try_publish(topic, self.qos, retain, payload) {
+            eprintln!("Failed to publish to {:?}: {:?}", topic, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(value: &str, mode: RowMode) -> ValueData {
+        let mut row = ValueData::new(
+            "Book".to_owned(),
+            "https://example.com".to_owned(),
+            ".price".to_owned(),
+            value.to_owned(),
+        );
+        row.mode = mode;
+        row
+    }
+
+    #[test]
+    fn value_rows_publish_value_and_number() {
+        let state = state_payload(&row("$12.50", RowMode::Value));
+        assert_eq!(state["value"], "$12.50");
+        assert_eq!(state["number"], 12.5);
+        assert!(state.get("text").is_none());
+    }
+
+    #[test]
+    fn availability_rows_have_no_number() {
+        let state = state_payload(&row("In stock", RowMode::Availability(Vec::new())));
+        assert_eq!(state["value"], "In stock");
+        assert!(state["number"].is_null());
+    }
+
+    #[test]
+    fn text_rows_publish_a_short_state_and_the_text_as_attribute() {
+        let page = "Release 2.0 with 300 fixes\n".repeat(100);
+        let mut text_row = row("", RowMode::Text { html: false });
+        crate::apply_new_value(
+            &mut text_row,
+            crate::FetchResult {
+                value: page.clone(),
+                ..Default::default()
+            },
+        );
+        let state = state_payload(&text_row);
+        let value = state["value"].as_str().unwrap();
+        assert!(value.starts_with("+100 -0 lines"));
+        assert!(value.chars().count() <= MAX_STATE_CHARS);
+        assert!(state["number"].is_null());
+        assert_eq!(state["text"], page);
+    }
+
+    #[test]
+    fn discovery_config_points_at_the_state_topic() {
+        let settings = MqttSettings::default();
+        let row = row("$1", RowMode::Value);
+        let config = discovery_config(&settings, &row);
+        let state_topic = format!("web_value_tracker/{}/state", row.id);
+        assert_eq!(config["name"], "Book");
+        assert_eq!(config["unique_id"], format!("web_value_tracker_{}", row.id));
+        assert_eq!(config["state_topic"], state_topic);
+        assert_eq!(config["json_attributes_topic"], state_topic);
+        assert_eq!(
+            discovery_topic(&settings, &row.id),
+            format!("homeassistant/sensor/web_value_tracker_{}/config", row.id)
+        );
+    }
+}
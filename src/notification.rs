@@ -0,0 +1,448 @@
+use chrono::{DateTime, Duration, Local, NaiveTime, Utc};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use crate::app::RowMode;
+
+const DEFAULT_COOLDOWN_MINUTES: i64 = 30;
+const DEFAULT_MAX_PER_RUN: usize = 3;
+/// Delivered alerts kept in the inbox, oldest dropped first.
+const MAX_INBOX_ENTRIES: usize = 200;
+
+/// A window of the day in which no notifications are shown.
+/// The window may wrap around midnight (e.g. 22:00 - 07:00).
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, PartialEq)]
+pub struct QuietHours {
+    pub enabled: bool,
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl Default for QuietHours {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            start: NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+            end: NaiveTime::from_hms_opt(7, 0, 0).unwrap(),
+        }
+    }
+}
+
+impl QuietHours {
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        if self.start <= self.end {
+            time >= self.start && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+/// Where notifications are delivered.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Channel {
+    Desktop,
+}
+
+impl Channel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Channel::Desktop => "Desktop",
+        }
+    }
+
+    pub fn get_channels() -> [Channel; 1] {
+        [Channel::Desktop]
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
+pub struct ChannelSettings {
+    pub channel: Channel,
+    pub enabled: bool,
+    pub quiet_hours: QuietHours,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
+#[serde(default)]
+pub struct NotificationSettings {
+    pub quiet_hours: QuietHours,
+    pub channels: Vec<ChannelSettings>,
+    /// Minimum minutes between two notifications for the same row.
+    pub cooldown_minutes: i64,
+    /// More changes than this in one delivery are collapsed into a single summary.
+    pub max_per_run: usize,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self {
+            quiet_hours: QuietHours::default(),
+            channels: Channel::get_channels()
+                .into_iter()
+                .map(|channel| ChannelSettings {
+                    channel,
+                    enabled: true,
+                    quiet_hours: QuietHours::default(),
+                })
+                .collect(),
+            cooldown_minutes: DEFAULT_COOLDOWN_MINUTES,
+            max_per_run: DEFAULT_MAX_PER_RUN,
+        }
+    }
+}
+
+/// A value change waiting to be shown to the user.
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
+pub struct Alert {
+    pub id: String,
+    pub name: String,
+    pub old_value: String,
+    pub new_value: String,
+    pub changed_at: DateTime<Utc>,
+    pub mode: RowMode,
+    /// Picture of the page taken on the change.
+    #[serde(default)]
+    pub screenshot: Option<String>,
+}
+
+impl Alert {
+    pub fn body(&self) -> String {
+        match &self.mode {
+            RowMode::Value | RowMode::Computed { .. } => format!(
+                "Value of: {:?} changed from: {:?} to: {:?}\nAt time: {:?}",
+                self.name,
+                self.old_value,
+                self.new_value,
+                crate::format_date_time(&self.changed_at),
+            ),
+            RowMode::Text { .. } => format!(
+                "Text of: {:?} changed: {}\nAt time: {:?}",
+                self.name,
+                crate::diff::summary(&self.old_value, &self.new_value),
+                crate::format_date_time(&self.changed_at),
+            ),
+            RowMode::Availability(_) => format!(
+                "{:?} is now: {}\nAt time: {:?}",
+                self.name,
+                self.new_value,
+                crate::format_date_time(&self.changed_at),
+            ),
+        }
+    }
+
+    /// The change in a few words, for summaries listing several alerts and the inbox.
+    fn short_change(&self) -> String {
+        match &self.mode {
+            RowMode::Value | RowMode::Computed { .. } => {
+                format!("{:?} -> {:?}", self.old_value, self.new_value)
+            }
+            RowMode::Availability(_) => format!("now {}", self.new_value),
+            RowMode::Text { .. } => {
+                let (added, removed) = crate::diff::count_changes(&self.old_value, &self.new_value);
+                format!("+{} -{} lines", added, removed)
+            }
+        }
+    }
+}
+
+/// Alerts waiting for quiet hours or a cooldown to end, and when each row was
+/// last notified about. Saved with the app so a restart doesn't lose them.
+#[derive(serde::Deserialize, serde::Serialize, Default)]
+#[serde(default)]
+pub struct NotifierState {
+    last_notified: HashMap<(Channel, String), DateTime<Utc>>,
+    deferred: HashMap<Channel, Vec<Alert>>,
+    /// Reports such as digests, which skip the cooldown but not quiet hours.
+    deferred_messages: HashMap<Channel, Vec<String>>,
+    /// Alerts already delivered, oldest first.
+    inbox: Vec<InboxEntry>,
+}
+
+/// A delivered alert as listed in the inbox, without the full values, which
+/// can be whole pages for text rows.
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
+pub struct InboxEntry {
+    pub id: String,
+    pub name: String,
+    pub change: String,
+    pub changed_at: DateTime<Utc>,
+    pub screenshot: Option<String>,
+}
+
+impl NotifierState {
+    pub fn inbox(&self) -> &[InboxEntry] {
+        &self.inbox
+    }
+
+    pub fn clear_inbox(&mut self) {
+        self.inbox.clear();
+    }
+
+    fn add_to_inbox(&mut self, alert: &Alert) {
+        // an alert delivered on several channels is listed once
+        if self
+            .inbox
+            .iter()
+            .any(|known| known.id == alert.id && known.changed_at == alert.changed_at)
+        {
+            return;
+        }
+        self.inbox.push(InboxEntry {
+            id: alert.id.clone(),
+            name: alert.name.clone(),
+            change: alert.short_change(),
+            changed_at: alert.changed_at,
+            screenshot: alert.screenshot.clone(),
+        });
+        if self.inbox.len() > MAX_INBOX_ENTRIES {
+            let excess = self.inbox.len() - MAX_INBOX_ENTRIES;
+            self.inbox.drain(..excess);
+        }
+    }
+}
+
+pub type SharedNotifierState = Arc<Mutex<NotifierState>>;
+
+/// Applies quiet hours, per-row cooldown and the per-run cap before showing alerts.
+/// Alerts that can't be shown yet are kept and merged per row until they can.
+pub struct Notifier {
+    settings: NotificationSettings,
+    state: SharedNotifierState,
+}
+
+impl Notifier {
+    pub fn new(settings: NotificationSettings, state: SharedNotifierState) -> Self {
+        Self { settings, state }
+    }
+
+    /// Queue the alerts of one fetch run and deliver whatever is allowed right now.
+    pub fn notify(&mut self, alerts: Vec<Alert>, now: DateTime<Local>) {
+        {
+            let mut state = self.state.lock().unwrap();
+            for channel_settings in self.settings.channels.iter().filter(|c| c.enabled) {
+                let pending = state.deferred.entry(channel_settings.channel).or_default();
+                for alert in &alerts {
+                    merge_alert(pending, alert.clone());
+                }
+            }
+        }
+        self.flush(now);
+    }
+
+    /// Deliver a message on `channel` now, or when its quiet hours end.
+    pub fn send_message(&mut self, channel: Channel, body: String, now: DateTime<Local>) {
+        self.state
+            .lock()
+            .unwrap()
+            .deferred_messages
+            .entry(channel)
+            .or_default()
+            .push(body);
+        self.flush(now);
+    }
+
+    fn is_quiet(&self, channel: Channel, now: DateTime<Local>) -> bool {
+        self.settings.quiet_hours.contains(now.time())
+            || self
+                .settings
+                .channels
+                .iter()
+                .any(|c| c.channel == channel && c.quiet_hours.contains(now.time()))
+    }
+
+    /// Deliver deferred alerts whose quiet hours and cooldown have passed.
+    pub fn flush(&mut self, now: DateTime<Local>) {
+        let cooldown = Duration::minutes(self.settings.cooldown_minutes.max(0));
+        let mut state = self.state.lock().unwrap();
+        let mut delivered = Vec::new();
+        let NotifierState {
+            last_notified,
+            deferred,
+            deferred_messages,
+            ..
+        } = &mut *state;
+        for (channel, messages) in deferred_messages.iter_mut() {
+            if !self.is_quiet(*channel, now) {
+                for body in messages.drain(..) {
+                    deliver(*channel, &body);
+                }
+            }
+        }
+        for channel_settings in &self.settings.channels {
+            let channel = channel_settings.channel;
+            if self.is_quiet(channel, now) {
+                continue;
+            }
+            let Some(pending) = deferred.get_mut(&channel) else {
+                continue;
+            };
+
+            let (ready, waiting): (Vec<Alert>, Vec<Alert>) = pending.drain(..).partition(|alert| {
+                last_notified
+                    .get(&(channel, alert.id.clone()))
+                    .map_or(true, |last| *last + cooldown <= now)
+            });
+            *pending = waiting;
+            if ready.is_empty() {
+                continue;
+            }
+
+            if self.settings.max_per_run > 0 && ready.len() > self.settings.max_per_run {
+                deliver(channel, &summary_body(&ready, now));
+            } else {
+                for alert in &ready {
+                    deliver(channel, &alert.body());
+                }
+            }
+            for alert in ready {
+                last_notified.insert((channel, alert.id.clone()), now.with_timezone(&Utc));
+                delivered.push(alert);
+            }
+        }
+        for alert in &delivered {
+            state.add_to_inbox(alert);
+        }
+    }
+}
+
+/// Collapse a new alert into an already pending one for the same row,
+/// keeping the oldest "from" value so the user sees the full change.
+fn merge_alert(pending: &mut Vec<Alert>, alert: Alert) {
+    if let Some(index) = pending.iter().position(|p| p.id == alert.id) {
+        let existing = &mut pending[index];
+        existing.new_value = alert.new_value;
+        existing.changed_at = alert.changed_at;
+        existing.screenshot = alert.screenshot;
+        if existing.new_value == existing.old_value {
+            pending.remove(index);
+        }
+    } else {
+        pending.push(alert);
+    }
+}
+
+fn summary_body(alerts: &[Alert], now: DateTime<Local>) -> String {
+    let mut body = format!("{} values changed:\n", alerts.len());
+    for alert in alerts {
+        body.push_str(&format!("{}: {}\n", alert.name, alert.short_change()));
+    }
+    body.push_str(&format!("At time: {:?}", crate::format_date_time(&now)));
+    body
+}
+
+pub fn deliver(channel: Channel, body: &str) {
+    match channel {
+        Channel::Desktop => crate::show_notifcation(body),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(hour: u32, minute: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    fn alert(id: &str, old_value: &str, new_value: &str) -> Alert {
+        Alert {
+            id: id.to_owned(),
+            name: id.to_owned(),
+            old_value: old_value.to_owned(),
+            new_value: new_value.to_owned(),
+            changed_at: Utc::now(),
+            mode: RowMode::Value,
+            screenshot: None,
+        }
+    }
+
+    #[test]
+    fn quiet_hours_within_a_day() {
+        let quiet_hours = QuietHours {
+            enabled: true,
+            start: time(13, 0),
+            end: time(15, 0),
+        };
+        assert!(!quiet_hours.contains(time(12, 59)));
+        assert!(quiet_hours.contains(time(13, 0)));
+        assert!(quiet_hours.contains(time(14, 30)));
+        assert!(!quiet_hours.contains(time(15, 0)));
+    }
+
+    #[test]
+    fn quiet_hours_past_midnight() {
+        let quiet_hours = QuietHours {
+            enabled: true,
+            ..Default::default()
+        };
+        assert!(quiet_hours.contains(time(22, 0)));
+        assert!(quiet_hours.contains(time(23, 59)));
+        assert!(quiet_hours.contains(time(0, 0)));
+        assert!(quiet_hours.contains(time(6, 59)));
+        assert!(!quiet_hours.contains(time(7, 0)));
+        assert!(!quiet_hours.contains(time(12, 0)));
+        assert!(!quiet_hours.contains(time(21, 59)));
+    }
+
+    #[test]
+    fn disabled_quiet_hours_contain_nothing() {
+        let quiet_hours = QuietHours::default();
+        assert!(!quiet_hours.contains(time(23, 0)));
+    }
+
+    #[test]
+    fn merge_keeps_oldest_from_value() {
+        let mut pending = vec![alert("a", "1", "2")];
+        merge_alert(&mut pending, alert("b", "5", "6"));
+        merge_alert(&mut pending, alert("a", "2", "3"));
+        assert_eq!(pending.len(), 2);
+        assert_eq!(pending[0].old_value, "1");
+        assert_eq!(pending[0].new_value, "3");
+    }
+
+    #[test]
+    fn merge_drops_change_that_was_undone() {
+        let mut pending = vec![alert("a", "1", "2")];
+        merge_alert(&mut pending, alert("a", "2", "1"));
+        assert!(pending.is_empty());
+    }
+
+    #[test]
+    fn state_survives_saving() {
+        let mut state = NotifierState::default();
+        state
+            .last_notified
+            .insert((Channel::Desktop, "a".to_owned()), Utc::now());
+        state
+            .deferred
+            .insert(Channel::Desktop, vec![alert("a", "1", "2")]);
+        let saved = ron::to_string(&state).unwrap();
+        let loaded: NotifierState = ron::from_str(&saved).unwrap();
+        assert_eq!(loaded.last_notified.len(), 1);
+        assert_eq!(loaded.deferred[&Channel::Desktop][0].new_value, "2");
+    }
+
+    #[test]
+    fn inbox_lists_an_alert_once_and_drops_the_oldest() {
+        let mut state = NotifierState::default();
+        let first = alert("a", "1", "2");
+        state.add_to_inbox(&first);
+        state.add_to_inbox(&first);
+        assert_eq!(state.inbox().len(), 1);
+        assert_eq!(state.inbox()[0].change, "\"1\" -> \"2\"");
+
+        for index in 0..MAX_INBOX_ENTRIES {
+            let mut later = alert("b", "1", "2");
+            later.changed_at = first.changed_at + Duration::seconds(index as i64 + 1);
+            state.add_to_inbox(&later);
+        }
+        assert_eq!(state.inbox().len(), MAX_INBOX_ENTRIES);
+        assert!(state.inbox().iter().all(|entry| entry.id == "b"));
+    }
+}
@@ -0,0 +1,146 @@
+use poll_promise::Promise;
+use std::{
+    error::Error,
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::browser::{self, BrowserOverrides};
+
+const PICK_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// Evaluations fail briefly while a page reloads, so only give up after this many in a row.
+const MAX_POLL_FAILURES: u32 = 8;
+
+/// Defines `robustSelector(element)`, the shortest selector matching only `element`,
+/// preferring ids and stable attributes over position in the page.
+pub const ROBUST_SELECTOR_JS: &str = r##"
+const unique = (selector) => {
+    try {
+        return document.querySelectorAll(selector).length === 1;
+    } catch (e) {
+        return false;
+    }
+};
+const robustSelector = (element) => {
+    if (element.id && unique('#' + CSS.escape(element.id))) {
+        return '#' + CSS.escape(element.id);
+    }
+    for (const attribute of ['data-testid', 'data-test', 'itemprop', 'name', 'aria-label']) {
+        const value = element.getAttribute(attribute);
+        const selector = `${element.localName}[${attribute}="${CSS.escape(value ?? '')}"]`;
+        if (value && unique(selector)) {
+            return selector;
+        }
+    }
+    // generated class names (e.g. "css-1x2y3z") change between deploys
+    const classes = [...element.classList]
+        .filter((name) => !/\d{3,}/.test(name))
+        .map((name) => '.' + CSS.escape(name))
+        .join('');
+    if (classes && unique(element.localName + classes)) {
+        return element.localName + classes;
+    }
+    const parts = [];
+    for (let node = element; node && node !== document.documentElement; node = node.parentElement) {
+        if (node.id && unique('#' + CSS.escape(node.id))) {
+            parts.unshift('#' + CSS.escape(node.id));
+            break;
+        }
+        let part = node.localName;
+        const siblings = node.parentElement
+            ? [...node.parentElement.children].filter((sibling) => sibling.localName === node.localName)
+            : [];
+        if (siblings.length > 1) {
+            part += `:nth-of-type(${siblings.indexOf(node) + 1})`;
+        }
+        parts.unshift(part);
+        if (unique(parts.join(' > '))) {
+            break;
+        }
+    }
+    return parts.join(' > ');
+};
+"##;
+
+/// Highlights the element under the mouse and remembers a selector for the clicked one.
+/// Safe to evaluate repeatedly: it only installs itself once per page and
+/// returns the picked selector, or null while nothing has been clicked.
+fn picker_js() -> String {
+    format!(
+        r##"
+(() => {{
+    if (!window.__wvtPicker) {{
+        window.__wvtPicker = true;
+        const box = document.createElement('div');
+        box.style.cssText = 'position:fixed;pointer-events:none;z-index:2147483647;' +
+            'border:2px solid #e0245e;background:rgba(224,36,94,0.15);';
+        document.documentElement.appendChild(box);
+        {}
+        document.addEventListener('mouseover', (event) => {{
+            const rect = event.target.getBoundingClientRect();
+            Object.assign(box.style, {{
+                left: rect.left + 'px',
+                top: rect.top + 'px',
+                width: rect.width + 'px',
+                height: rect.height + 'px',
+            }});
+        }}, true);
+        document.addEventListener('click', (event) => {{
+            event.preventDefault();
+            event.stopPropagation();
+            box.style.borderColor = '#17bf63';
+            window.__wvtPicked = robustSelector(event.target);
+        }}, true);
+    }}
+    return window.__wvtPicked ?? null;
+}})()
+"##,
+        ROBUST_SELECTOR_JS
+    )
+}
+
+/// Open `url` in a visible Chrome window and wait for the user to click an element.
+/// Resolves to a selector matching only that element.
+pub fn pick_selector_promise(
+    url: String,
+    overrides: BrowserOverrides,
+) -> Promise<Result<String, String>> {
+    Promise::spawn_thread("selector_picker", move || {
+        pick_selector(&url, &overrides).map_err(|e| e.to_string())
+    })
+}
+
+fn pick_selector(url: &str, overrides: &BrowserOverrides) -> Result<String, Box<dyn Error>> {
+    let mut settings = browser::effective_settings(overrides);
+    if settings.window_width == 0 || settings.window_height == 0 {
+        settings.window_width = 1280;
+        settings.window_height = 900;
+    }
+    let (_browser, tab) = browser::launch(&settings, false)?;
+    tab.navigate_to(url)?.wait_until_navigated()?;
+
+    let picker_js = picker_js();
+    let started = Instant::now();
+    let mut failures = 0;
+    while started.elapsed() < PICK_TIMEOUT {
+        match tab.evaluate(&picker_js, false) {
+            Ok(result) => {
+                failures = 0;
+                if let Some(selector) = result.value.as_ref().and_then(|value| value.as_str()) {
+                    return Ok(selector.to_owned());
+                }
+            }
+            Err(e) if failures >= MAX_POLL_FAILURES => {
+                return Err(format!("the browser window was closed ({})", e).into());
+            }
+            Err(_) => failures += 1,
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+    Err(format!(
+        "no element was picked within {} minutes",
+        PICK_TIMEOUT.as_secs() / 60
+    )
+    .into())
+}
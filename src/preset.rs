@@ -0,0 +1,159 @@
+use regex::Regex;
+
+/// Presets shipped with the app, see the comments in the file for its format.
+const BUNDLED_PRESETS: &str = include_str!("../assets/presets.ron");
+const USER_PRESETS_FILE: &str = "presets.ron";
+const USER_PRESETS_TEMPLATE: &str = "\
+// Your own selector presets, offered before the bundled ones. For example:
+// (
+//     name: \"Shop Price\",
+//     hosts: [\"shop.example.com\"],
+//     selectors: [\"#price\", \".price\"],
+//     transforms: [Regex(r\"[\\d.,]+\"), Replace(\",\", \"\")],
+// ),
+[
+]
+";
+
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
+pub struct Preset {
+    pub name: String,
+    /// Host patterns the preset is meant for, `*` matches one part of a host
+    /// between dots, so `shop.*` matches `shop.de` but not `shop.evil.com`.
+    #[serde(default)]
+    pub hosts: Vec<String>,
+    /// Selectors in order of preference, for sites that vary their markup.
+    pub selectors: Vec<String>,
+    #[serde(default)]
+    pub transforms: Vec<Transform>,
+}
+
+impl Preset {
+    pub fn matches_link(&self, link: &str) -> bool {
+        let Some(host) = host_of(link) else {
+            return false;
+        };
+        self.hosts
+            .iter()
+            .any(|pattern| host_matches(pattern, &host))
+    }
+}
+
+/// A clean-up step applied to the text read from the page.
+#[derive(serde::Deserialize, serde::Serialize, schemars::JsonSchema, Clone, PartialEq)]
+pub enum Transform {
+    /// Keep the first match, or its first capture group if the pattern has one.
+    Regex(String),
+    Replace(String, String),
+    CollapseWhitespace,
+}
+
+/// Apply `transforms` in order. A regex that doesn't match leaves the value empty,
+/// so the fetch counts as failed rather than tracking unrelated text.
+pub fn apply_transforms(transforms: &[Transform], value: String) -> String {
+    transforms
+        .iter()
+        .fold(value, |value, transform| match transform {
+            Transform::Regex(pattern) => match Regex::new(pattern) {
+                Ok(regex) => regex
+                    .captures(&value)
+                    .and_then(|captures| captures.get(1).or_else(|| captures.get(0)))
+                    .map(|m| m.as_str().to_owned())
+                    .unwrap_or_default(),
+                Err(e) => {
+                    eprintln!("Invalid transform regex {:?}: {:?}", pattern, e);
+                    value
+                }
+            },
+            Transform::Replace(from, to) => value.replace(from, to),
+            Transform::CollapseWhitespace => value.split_whitespace().collect::<Vec<_>>().join(" "),
+        })
+        .trim()
+        .to_owned()
+}
+
+/// User presets followed by the bundled ones. Problems reading the user file
+/// are returned next to the presets that could be loaded.
+pub fn load_presets() -> (Vec<Preset>, Option<String>) {
+    let mut presets = Vec::new();
+    let mut error = None;
+    match read_user_presets() {
+        Ok(user_presets) => presets.extend(user_presets),
+        Err(e) => {
+            eprintln!("Failed to load user presets: {}", e);
+            error = Some(e);
+        }
+    }
+    presets
+        .extend(ron::from_str::<Vec<Preset>>(BUNDLED_PRESETS).expect("bundled presets are valid"));
+    (presets, error)
+}
+
+/// Where users can add their own presets.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn user_presets_path() -> Option<std::path::PathBuf> {
+    eframe::storage_dir(crate::APP_NAME).map(|dir| dir.join(USER_PRESETS_FILE))
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn user_presets_path() -> Option<std::path::PathBuf> {
+    None
+}
+
+fn read_user_presets() -> Result<Vec<Preset>, String> {
+    let Some(path) = user_presets_path() else {
+        return Ok(Vec::new());
+    };
+    if !path.exists() {
+        // leave an example behind so there's something to edit
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        std::fs::write(&path, USER_PRESETS_TEMPLATE).map_err(|e| e.to_string())?;
+    }
+    let text = std::fs::read_to_string(&path).map_err(|e| format!("{:?}: {}", path, e))?;
+    ron::from_str(&text).map_err(|e| format!("{:?}: {}", path, e))
+}
+
+/// Host of an http(s) link, without "www." and port.
+fn host_of(link: &str) -> Option<String> {
+    let rest = link.trim().split_once("://").map_or(link, |(_, rest)| rest);
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let host = authority.rsplit('@').next()?.split(':').next()?;
+    let host = host.to_lowercase();
+    let host = host.strip_prefix("www.").unwrap_or(&host);
+    (!host.is_empty()).then(|| host.to_owned())
+}
+
+fn host_matches(pattern: &str, host: &str) -> bool {
+    let pattern = format!(
+        "^{}$",
+        regex::escape(&pattern.to_lowercase()).replace(r"\*", "[^./]*")
+    );
+    Regex::new(&pattern).is_ok_and(|regex| regex.is_match(host))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wildcard_matches_one_host_part() {
+        assert!(host_matches("amazon.*", "amazon.de"));
+        assert!(host_matches("*.amazon.*", "smile.amazon.com"));
+        assert!(host_matches("amazon.co.*", "amazon.co.uk"));
+        assert!(!host_matches("amazon.*", "amazon.evil.com"));
+        assert!(!host_matches("amazon.*", "amazon.co.uk"));
+        assert!(!host_matches("*.amazon.*", "amazon.com"));
+    }
+
+    #[test]
+    fn bundled_presets_match_shop_links() {
+        let presets: Vec<Preset> = ron::from_str(BUNDLED_PRESETS).unwrap();
+        let matching = |link: &str| presets.iter().any(|preset| preset.matches_link(link));
+        assert!(matching("https://www.amazon.com/dp/0000000000"));
+        assert!(matching("https://www.amazon.co.uk/dp/0000000000"));
+        assert!(matching("https://www.ebay.com.au/itm/1"));
+        assert!(!matching("https://amazon.evil.com/dp/0000000000"));
+    }
+}
@@ -0,0 +1,130 @@
+use base64::Engine;
+use headless_chrome::{protocol::cdp::Page, Tab};
+use std::{
+    error::Error,
+    path::{Path, PathBuf},
+    sync::{OnceLock, RwLock},
+};
+
+const SCREENSHOTS_DIR: &str = "screenshots";
+
+/// When to keep a picture of the page a value was read from.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct ScreenshotSettings {
+    pub on_change: bool,
+    pub on_failure: bool,
+    /// Capture the whole page rather than only the element the value was read from.
+    /// Failures always capture the whole page.
+    pub full_page: bool,
+}
+
+fn global_settings() -> &'static RwLock<ScreenshotSettings> {
+    static SETTINGS: OnceLock<RwLock<ScreenshotSettings>> = OnceLock::new();
+    SETTINGS.get_or_init(Default::default)
+}
+
+/// Use `settings` for fetches started from now on.
+pub fn set_global_settings(settings: &ScreenshotSettings) {
+    *global_settings().write().unwrap() = settings.clone();
+}
+
+pub fn settings() -> ScreenshotSettings {
+    global_settings().read().unwrap().clone()
+}
+
+/// Where screenshots are kept, one folder per row.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn screenshots_dir() -> Option<PathBuf> {
+    eframe::storage_dir(crate::APP_NAME).map(|dir| dir.join(SCREENSHOTS_DIR))
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn screenshots_dir() -> Option<PathBuf> {
+    None
+}
+
+/// Save a PNG of the element matching `css_selector`, or of the whole page
+/// without one, and return its path. Failing to capture is logged, not fatal.
+pub fn take(tab: &Tab, row_id: &str, css_selector: Option<&str>) -> Option<String> {
+    match capture(tab, css_selector).and_then(|png| save(row_id, &png)) {
+        Ok(path) => Some(path),
+        Err(e) => {
+            eprintln!("Failed to take a screenshot: {}", e);
+            None
+        }
+    }
+}
+
+fn capture(tab: &Tab, css_selector: Option<&str>) -> Result<Vec<u8>, Box<dyn Error>> {
+    if let Some(css_selector) = css_selector {
+        let element = tab.find_element(css_selector)?;
+        return Ok(element.capture_screenshot(Page::CaptureScreenshotFormatOption::Png)?);
+    }
+    let size = tab
+        .evaluate(
+            "JSON.stringify([document.documentElement.scrollWidth, document.documentElement.scrollHeight])",
+            false,
+        )?
+        .value
+        .and_then(|value| value.as_str().map(str::to_owned))
+        .ok_or("couldn't measure the page")?;
+    let (width, height): (f64, f64) = serde_json::from_str(&size)?;
+    let data = tab
+        .call_method(Page::CaptureScreenshot {
+            format: Some(Page::CaptureScreenshotFormatOption::Png),
+            quality: None,
+            clip: Some(Page::Viewport {
+                x: 0.0,
+                y: 0.0,
+                width,
+                height,
+                scale: 1.0,
+            }),
+            from_surface: Some(true),
+            capture_beyond_viewport: Some(true),
+        })?
+        .data;
+    Ok(base64::prelude::BASE64_STANDARD.decode(data)?)
+}
+
+fn save(row_id: &str, png: &[u8]) -> Result<String, Box<dyn Error>> {
+    let dir = screenshots_dir()
+        .ok_or("no storage directory")?
+        .join(row_id);
+    std::fs::create_dir_all(&dir)?;
+    let path = dir.join(format!(
+        "{}.png",
+        chrono::Utc::now().format("%Y%m%d-%H%M%S%.3f")
+    ));
+    std::fs::write(&path, png)?;
+    Ok(path.display().to_string())
+}
+
+/// Delete a screenshot that's no longer referenced.
+pub fn remove(path: &str) {
+    if let Err(e) = std::fs::remove_file(path) {
+        eprintln!("Failed to remove screenshot {:?}: {}", path, e);
+    }
+}
+
+/// Delete a screenshot of a row that was never added, with its folder.
+pub fn remove_draft(path: &str) {
+    remove(path);
+    if let Some(dir) = Path::new(path).parent() {
+        // other drafts have folders of their own, so this one is empty now
+        let _ = std::fs::remove_dir(dir);
+    }
+}
+
+/// Delete all screenshots of a deleted row.
+pub fn remove_row(row_id: &str) {
+    let Some(dir) = screenshots_dir().map(|dir| dir.join(row_id)) else {
+        return;
+    };
+    if dir.exists() {
+        if let Err(e) = std::fs::remove_dir_all(&dir) {
+            eprintln!("Failed to remove screenshots in {:?}: {}", dir, e);
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use headless_chrome::{
+    protocol::cdp::{Page, Runtime},
+    Tab,
+};
+use serde_json::Value;
+use std::{error::Error, time::Duration};
+
+/// Longest a row's script may run, including any promise it returns.
+const SCRIPT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Run a row's extractor script and return its result as text.
+///
+/// The script runs in an isolated world: it sees the page's DOM but not its
+/// JavaScript, so it can't break the page or be fooled by it. It's either an
+/// expression, or a function body when it doesn't parse as one, and may be async.
+pub fn run_extractor(tab: &Tab, script: &str) -> Result<String, Box<dyn Error>> {
+    let world = tab.call_method(Page::CreateIsolatedWorld {
+        // the main frame has the tab's id
+        frame_id: tab.get_target_id().clone(),
+        world_name: Some("web_value_tracker".to_owned()),
+        grant_univeral_access: None,
+    })?;
+    let timeout_ms = SCRIPT_TIMEOUT.as_millis();
+    let expression = extractor_source(script, |source| {
+        tab.call_method(Runtime::CompileScript {
+            expression: source.to_owned(),
+            source_url: String::new(),
+            persist_script: false,
+            execution_context_id: Some(world.execution_context_id),
+        })
+        .is_ok_and(|compiled| compiled.exception_details.is_none())
+    });
+
+    let evaluated = tab.call_method(Runtime::Evaluate {
+        expression,
+        return_by_value: Some(true),
+        generate_preview: None,
+        silent: Some(true),
+        context_id: Some(world.execution_context_id),
+        await_promise: Some(true),
+        include_command_line_api: None,
+        user_gesture: None,
+        object_group: None,
+        throw_on_side_effect: None,
+        // stops scripts stuck in a synchronous loop, which the race can't
+        timeout: Some(timeout_ms as f64),
+        disable_breaks: None,
+        repl_mode: None,
+        allow_unsafe_eval_blocked_by_csp: None,
+        unique_context_id: None,
+    })?;
+    if let Some(details) = evaluated.exception_details {
+        let message = details
+            .exception
+            .and_then(|exception| exception.description)
+            .unwrap_or(details.text);
+        return Err(format!("script failed: {}", message).into());
+    }
+
+    match evaluated.result.value {
+        Some(Value::String(text)) => Ok(text.trim().to_owned()),
+        Some(Value::Number(number)) => Ok(number.to_string()),
+        Some(Value::Bool(flag)) => Ok(flag.to_string()),
+        Some(Value::Null) | None => Ok(String::new()),
+        Some(other) => {
+            Err(format!("script returned {}, expected a string or number", other).into())
+        }
+    }
+}
+
+/// The script as an expression when `compiles` accepts that, otherwise as a
+/// function body, raced against the timeout.
+fn extractor_source(script: &str, compiles: impl Fn(&str) -> bool) -> String {
+    let as_expression = with_timeout(&format!(
+        "return ({});",
+        script.trim().trim_end_matches(';')
+    ));
+    if compiles(&as_expression) {
+        as_expression
+    } else {
+        with_timeout(script)
+    }
+}
+
+fn with_timeout(body: &str) -> String {
+    let timeout_ms = SCRIPT_TIMEOUT.as_millis();
+    format!(
+        r##"
+Promise.race([
+    (async () => {{
+{}
+    }})(),
+    new Promise((_, reject) => setTimeout(
+        () => reject(new Error('timed out after {} ms')), {})),
+])
+"##,
+        body, timeout_ms, timeout_ms
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expression_mentioning_return_stays_an_expression() {
+        let script = "document.querySelector('.return-price').textContent";
+        let source = extractor_source(script, |_| true);
+        assert!(source.contains(&format!("return ({});", script)));
+    }
+
+    #[test]
+    fn statements_become_a_function_body() {
+        let script = "const price = document.querySelector('.price');\nreturn price.textContent;";
+        let source = extractor_source(script, |source| !source.contains("return (const"));
+        assert!(source.contains(script));
+        assert!(!source.contains("return (const"));
+    }
+}
@@ -0,0 +1,388 @@
+use headless_chrome::{protocol::cdp::Network::CookieParam, Tab};
+use poll_promise::Promise;
+use std::{
+    error::Error,
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::browser::{self, BrowserOverrides};
+use crate::picker::ROBUST_SELECTOR_JS;
+use crate::steps::{self, Step, StepAction};
+
+const LOGGED_IN_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+const RECORD_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+const MAX_POLL_FAILURES: u32 = 8;
+
+/// How a row gets at pages that are only visible when signed in.
+#[derive(serde::Deserialize, serde::Serialize, schemars::JsonSchema, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct SessionSettings {
+    /// Sent with every fetch and updated with what the site sets.
+    pub cookies: Vec<StoredCookie>,
+    /// Page the login steps start on, empty to run them on the row's own page.
+    pub login_url: String,
+    /// Only present when signed in. Login steps are skipped when it's found.
+    pub logged_in_selector: String,
+    pub login_steps: Vec<Step>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, schemars::JsonSchema, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct StoredCookie {
+    pub name: String,
+    pub value: String,
+    pub domain: String,
+    #[serde(default)]
+    pub path: String,
+    #[serde(default)]
+    pub secure: bool,
+    #[serde(default)]
+    pub http_only: bool,
+    /// Unix time, `None` for session cookies.
+    #[serde(default)]
+    pub expires: Option<f64>,
+}
+
+impl StoredCookie {
+    fn is_expired(&self, now: f64) -> bool {
+        self.expires.is_some_and(|expires| expires <= now)
+    }
+}
+
+/// Read cookies exported from a browser, either as JSON (e.g. from the
+/// "Cookie-Editor" extension) or in the Netscape cookies.txt format.
+pub fn import_cookies(path: &str) -> Result<Vec<StoredCookie>, String> {
+    let text = std::fs::read_to_string(path.trim()).map_err(|e| e.to_string())?;
+    let cookies = if text.trim_start().starts_with('[') {
+        parse_json_cookies(&text)?
+    } else {
+        parse_netscape_cookies(&text)
+    };
+    if cookies.is_empty() {
+        return Err("no cookies found in the file".to_owned());
+    }
+    Ok(cookies)
+}
+
+fn parse_json_cookies(text: &str) -> Result<Vec<StoredCookie>, String> {
+    #[derive(serde::Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct ExportedCookie {
+        name: String,
+        value: String,
+        domain: String,
+        #[serde(default)]
+        path: String,
+        #[serde(default)]
+        secure: bool,
+        #[serde(default)]
+        http_only: bool,
+        #[serde(default, alias = "expires")]
+        expiration_date: Option<f64>,
+    }
+
+    let exported: Vec<ExportedCookie> = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    Ok(exported
+        .into_iter()
+        .map(|cookie| StoredCookie {
+            name: cookie.name,
+            value: cookie.value,
+            domain: cookie.domain,
+            path: cookie.path,
+            secure: cookie.secure,
+            http_only: cookie.http_only,
+            expires: cookie.expiration_date,
+        })
+        .collect())
+}
+
+fn parse_netscape_cookies(text: &str) -> Vec<StoredCookie> {
+    text.lines()
+        .filter_map(|line| {
+            // curl marks HttpOnly cookies with a prefix on otherwise commented out lines
+            let (line, http_only) = match line.strip_prefix("#HttpOnly_") {
+                Some(line) => (line, true),
+                None if line.starts_with('#') => return None,
+                None => (line, false),
+            };
+            let fields: Vec<&str> = line.trim_end_matches(['\r', '\n']).split('\t').collect();
+            let [domain, _include_subdomains, path, secure, expires, name, value] = fields[..]
+            else {
+                return None;
+            };
+            Some(StoredCookie {
+                name: name.to_owned(),
+                value: value.to_owned(),
+                domain: domain.to_owned(),
+                path: path.to_owned(),
+                secure: secure.eq_ignore_ascii_case("TRUE"),
+                http_only,
+                expires: expires.parse().ok().filter(|&expires: &f64| expires > 0.0),
+            })
+        })
+        .collect()
+}
+
+pub fn set_cookies(tab: &Tab, cookies: &[StoredCookie]) -> Result<(), Box<dyn Error>> {
+    let params = cookies
+        .iter()
+        .map(|cookie| CookieParam {
+            name: cookie.name.clone(),
+            value: cookie.value.clone(),
+            url: None,
+            domain: Some(cookie.domain.clone()),
+            path: (!cookie.path.is_empty()).then(|| cookie.path.clone()),
+            secure: Some(cookie.secure),
+            http_only: Some(cookie.http_only),
+            same_site: None,
+            expires: cookie.expires,
+            priority: None,
+            same_party: None,
+            source_scheme: None,
+            source_port: None,
+            partition_key: None,
+        })
+        .collect();
+    tab.set_cookies(params)?;
+    Ok(())
+}
+
+/// Cookies the browser has for the current page.
+pub fn read_cookies(tab: &Tab) -> Result<Vec<StoredCookie>, Box<dyn Error>> {
+    Ok(tab
+        .get_cookies()?
+        .into_iter()
+        .map(|cookie| StoredCookie {
+            name: cookie.name,
+            value: cookie.value,
+            domain: cookie.domain,
+            path: cookie.path,
+            secure: cookie.secure,
+            http_only: cookie.http_only,
+            expires: (!cookie.session).then_some(cookie.expires),
+        })
+        .collect())
+}
+
+/// Update `jar` with `fresh` cookies, keeping cookies of other sites
+/// and dropping the ones that expired.
+pub fn merge_cookies(jar: &mut Vec<StoredCookie>, fresh: Vec<StoredCookie>) {
+    merge_cookies_at(jar, fresh, chrono::Utc::now().timestamp() as f64);
+}
+
+fn merge_cookies_at(jar: &mut Vec<StoredCookie>, fresh: Vec<StoredCookie>, now: f64) {
+    for cookie in fresh {
+        match jar.iter_mut().find(|existing| {
+            existing.name == cookie.name
+                && existing.domain == cookie.domain
+                && existing.path == cookie.path
+        }) {
+            Some(existing) => *existing = cookie,
+            None => jar.push(cookie),
+        }
+    }
+    jar.retain(|cookie| !cookie.is_expired(now));
+}
+
+/// Run the login steps if the tab, already on `url`, doesn't look signed in,
+/// then come back to `url`. Returns whether the steps ran.
+pub fn ensure_logged_in(
+    tab: &Tab,
+    url: &str,
+    session: &SessionSettings,
+) -> Result<bool, Box<dyn Error>> {
+    if session.login_steps.is_empty() {
+        return Ok(false);
+    }
+    tab.wait_until_navigated()?;
+    if !session.logged_in_selector.is_empty()
+        && tab
+            .wait_for_element_with_custom_timeout(
+                &session.logged_in_selector,
+                LOGGED_IN_CHECK_TIMEOUT,
+            )
+            .is_ok()
+    {
+        return Ok(false);
+    }
+
+    println!("Signing in for {:?}", url);
+    if !session.login_url.is_empty() {
+        tab.navigate_to(&session.login_url)?
+            .wait_until_navigated()?;
+    }
+    steps::run_steps(tab, &session.login_steps).map_err(|e| format!("login {}", e))?;
+    // the last step usually submits a form, let it finish before leaving
+    let _ = tab.wait_until_navigated();
+    tab.navigate_to(url)?;
+    Ok(true)
+}
+
+/// Records clicks and typed text into sessionStorage, which survives the
+/// navigations a login form makes. Evaluating it again drains what was recorded.
+fn recorder_js() -> String {
+    format!(
+        r##"
+(() => {{
+    const key = '__wvtRecordedSteps';
+    const record = (step) => {{
+        const steps = JSON.parse(sessionStorage.getItem(key) || '[]');
+        const last = steps[steps.length - 1];
+        // typing into the same field again replaces the text
+        if (step.action === 'Type' && last && last.action === 'Type' && last.selector === step.selector) {{
+            steps.pop();
+        }}
+        steps.push(step);
+        sessionStorage.setItem(key, JSON.stringify(steps));
+    }};
+    if (!window.__wvtRecorder) {{
+        window.__wvtRecorder = true;
+        {}
+        document.addEventListener('click', (event) => {{
+            const target = event.target;
+            if (target.matches('input:not([type=submit]):not([type=button]):not([type=checkbox]):not([type=radio]), textarea')) {{
+                return;
+            }}
+            record({{ action: 'Click', selector: robustSelector(target), text: '' }});
+        }}, true);
+        document.addEventListener('change', (event) => {{
+            const target = event.target;
+            if (target.matches('select')) {{
+                record({{ action: 'SelectOption', selector: robustSelector(target), text: target.value }});
+            }} else if (target.matches('input, textarea') && typeof target.value === 'string'
+                && !['checkbox', 'radio', 'submit', 'button'].includes(target.type)) {{
+                record({{ action: 'Type', selector: robustSelector(target), text: target.value }});
+            }}
+        }}, true);
+    }}
+    const steps = sessionStorage.getItem(key) || '[]';
+    sessionStorage.removeItem(key);
+    return steps;
+}})()
+"##,
+        ROBUST_SELECTOR_JS
+    )
+}
+
+/// Open `url` in a visible Chrome window and record what the user does until
+/// they close it, to replay later as login steps.
+pub fn record_steps_promise(
+    url: String,
+    overrides: BrowserOverrides,
+) -> Promise<Result<Vec<Step>, String>> {
+    Promise::spawn_thread("login_recorder", move || {
+        record_steps(&url, &overrides).map_err(|e| e.to_string())
+    })
+}
+
+fn record_steps(url: &str, overrides: &BrowserOverrides) -> Result<Vec<Step>, Box<dyn Error>> {
+    let (_browser, tab) = browser::launch(&browser::effective_settings(overrides), false)?;
+    tab.navigate_to(url)?.wait_until_navigated()?;
+
+    let recorder_js = recorder_js();
+    let mut recorded: Vec<Step> = Vec::new();
+    let started = Instant::now();
+    let mut failures = 0;
+    while started.elapsed() < RECORD_TIMEOUT && failures < MAX_POLL_FAILURES {
+        match tab.evaluate(&recorder_js, false) {
+            Ok(result) => {
+                failures = 0;
+                if let Some(json) = result.value.as_ref().and_then(|value| value.as_str()) {
+                    for step in serde_json::from_str::<Vec<Step>>(json)? {
+                        let replaces_last = step.action == StepAction::Type
+                            && recorded.last().is_some_and(|last| {
+                                last.action == StepAction::Type && last.selector == step.selector
+                            });
+                        if replaces_last {
+                            recorded.pop();
+                        }
+                        recorded.push(step);
+                    }
+                }
+            }
+            // the user closing the window ends the recording
+            Err(_) => failures += 1,
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+    if recorded.is_empty() {
+        return Err("nothing was recorded".into());
+    }
+    Ok(recorded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cookie(name: &str, value: &str, expires: Option<f64>) -> StoredCookie {
+        StoredCookie {
+            name: name.to_owned(),
+            value: value.to_owned(),
+            domain: ".example.com".to_owned(),
+            path: "/".to_owned(),
+            secure: false,
+            http_only: false,
+            expires,
+        }
+    }
+
+    #[test]
+    fn netscape_cookies_skip_comments_and_malformed_lines() {
+        let text = "# Netscape HTTP Cookie File\n\
+            \n\
+            .example.com\tTRUE\t/\tTRUE\t1999999999\tsid\tabc\r\n\
+            #HttpOnly_.example.com\tTRUE\t/account\tFALSE\t0\ttoken\txyz\n\
+            .example.com\tTRUE\t/\tmissing fields\n\
+            not a cookie at all\n";
+        let cookies = parse_netscape_cookies(text);
+        assert_eq!(cookies.len(), 2);
+        assert_eq!(cookies[0].name, "sid");
+        assert_eq!(cookies[0].value, "abc");
+        assert!(cookies[0].secure && !cookies[0].http_only);
+        assert_eq!(cookies[0].expires, Some(1999999999.0));
+        assert_eq!(cookies[1].path, "/account");
+        assert!(cookies[1].http_only && !cookies[1].secure);
+        assert_eq!(cookies[1].expires, None);
+    }
+
+    #[test]
+    fn json_cookies_are_parsed() {
+        let text = r#"[
+            {"name": "sid", "value": "abc", "domain": ".example.com", "path": "/",
+             "secure": true, "httpOnly": true, "expirationDate": 1999999999.5},
+            {"name": "theme", "value": "dark", "domain": "example.com"}
+        ]"#;
+        let cookies = parse_json_cookies(text).unwrap();
+        assert_eq!(cookies.len(), 2);
+        assert!(cookies[0].http_only && cookies[0].secure);
+        assert_eq!(cookies[0].expires, Some(1999999999.5));
+        assert_eq!(cookies[1].path, "");
+        assert_eq!(cookies[1].expires, None);
+
+        assert!(parse_json_cookies(r#"[{"name": "sid"}]"#).is_err());
+        assert!(parse_json_cookies("[{").is_err());
+    }
+
+    #[test]
+    fn merging_replaces_matches_and_drops_expired_cookies() {
+        let mut jar = vec![
+            cookie("sid", "old", Some(2000.0)),
+            cookie("stale", "x", Some(500.0)),
+            cookie("session", "y", None),
+        ];
+        merge_cookies_at(
+            &mut jar,
+            vec![
+                cookie("sid", "new", Some(3000.0)),
+                cookie("gone", "z", Some(1000.0)),
+            ],
+            1000.0,
+        );
+        let names: Vec<_> = jar.iter().map(|cookie| cookie.name.as_str()).collect();
+        assert_eq!(names, ["sid", "session"]);
+        assert_eq!(jar[0].value, "new");
+    }
+}
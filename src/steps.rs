@@ -0,0 +1,253 @@
+use egui::{TextEdit, Ui};
+use headless_chrome::Tab;
+use std::{error::Error, thread, time::Duration};
+
+/// Longest pause a "Wait ms" step may ask for, a minute like the page load timeout.
+pub const MAX_WAIT_MS: u64 = 60_000;
+
+#[derive(serde::Deserialize, serde::Serialize, schemars::JsonSchema, Clone, Copy, PartialEq)]
+pub enum StepAction {
+    Click,
+    Type,
+    /// Choose the option of a `<select>` with the given value or label.
+    SelectOption,
+    WaitForSelector,
+    /// Scroll the element into view, or to the bottom of the page without a selector.
+    Scroll,
+    /// Pause for the number of milliseconds in `text`.
+    WaitMs,
+}
+
+impl StepAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StepAction::Click => "Click",
+            StepAction::Type => "Type",
+            StepAction::SelectOption => "Select",
+            StepAction::WaitForSelector => "Wait for",
+            StepAction::Scroll => "Scroll",
+            StepAction::WaitMs => "Wait ms",
+        }
+    }
+
+    pub fn get_actions() -> [StepAction; 6] {
+        [
+            StepAction::Click,
+            StepAction::Type,
+            StepAction::SelectOption,
+            StepAction::WaitForSelector,
+            StepAction::Scroll,
+            StepAction::WaitMs,
+        ]
+    }
+
+    fn uses_selector(&self) -> bool {
+        !matches!(self, StepAction::WaitMs)
+    }
+
+    fn uses_text(&self) -> bool {
+        matches!(
+            self,
+            StepAction::Type | StepAction::SelectOption | StepAction::WaitMs
+        )
+    }
+
+    fn text_hint(&self) -> &'static str {
+        match self {
+            StepAction::SelectOption => "option value or label",
+            StepAction::WaitMs => "milliseconds",
+            _ => "text",
+        }
+    }
+}
+
+/// One browser interaction, run against the element matching `selector`.
+/// Actions that don't need an element ignore it.
+#[derive(serde::Deserialize, serde::Serialize, schemars::JsonSchema, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Step {
+    pub action: StepAction,
+    pub selector: String,
+    /// What to type, for actions that take text.
+    #[serde(default)]
+    pub text: String,
+}
+
+/// Run `steps` in order, stopping at the first one that fails.
+pub fn run_steps(tab: &Tab, steps: &[Step]) -> Result<(), Box<dyn Error>> {
+    for (index, step) in steps.iter().enumerate() {
+        run_step(tab, step).map_err(|e| {
+            format!(
+                "step {} ({} {:?}) failed: {}",
+                index + 1,
+                step.action.as_str(),
+                step.selector,
+                e
+            )
+        })?;
+    }
+    Ok(())
+}
+
+fn run_step(tab: &Tab, step: &Step) -> Result<(), Box<dyn Error>> {
+    match step.action {
+        StepAction::Click => {
+            tab.wait_for_element(&step.selector)?.click()?;
+        }
+        StepAction::Type => {
+            tab.wait_for_element(&step.selector)?
+                .type_into(&step.text)?;
+        }
+        StepAction::SelectOption => {
+            tab.wait_for_element(&step.selector)?;
+            select_option(tab, &step.selector, &step.text)?;
+        }
+        StepAction::WaitForSelector => {
+            tab.wait_for_element(&step.selector)?;
+        }
+        StepAction::Scroll if step.selector.is_empty() => {
+            tab.evaluate("window.scrollTo(0, document.body.scrollHeight)", false)?;
+        }
+        StepAction::Scroll => {
+            tab.wait_for_element(&step.selector)?.scroll_into_view()?;
+        }
+        StepAction::WaitMs => {
+            // rows saved before the limit may still wait longer
+            let millis = parse_millis(&step.text)?.min(MAX_WAIT_MS);
+            thread::sleep(Duration::from_millis(millis));
+        }
+    }
+    Ok(())
+}
+
+fn parse_millis(text: &str) -> Result<u64, String> {
+    text.trim()
+        .parse()
+        .map_err(|_| format!("{:?} isn't a number of milliseconds", text))
+}
+
+/// Why `steps` can't run, if they can't.
+pub fn validate_steps(steps: &[Step]) -> Result<(), String> {
+    for (index, step) in steps.iter().enumerate() {
+        if step.action == StepAction::WaitMs {
+            let millis =
+                parse_millis(&step.text).map_err(|e| format!("step {}: {}", index + 1, e))?;
+            if millis > MAX_WAIT_MS {
+                return Err(format!(
+                    "step {}: waits longer than {} ms",
+                    index + 1,
+                    MAX_WAIT_MS
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Pick the option by value or label and fire `change` like a user would, so
+/// pages that update prices on selection notice it.
+fn select_option(tab: &Tab, css_selector: &str, option: &str) -> Result<(), Box<dyn Error>> {
+    let js = format!(
+        r##"
+((select, wanted) => {{
+    const option = [...select.options].find((option) => option.value === wanted)
+        ?? [...select.options].find((option) => option.text.trim() === wanted);
+    if (!option) {{
+        return false;
+    }}
+    select.value = option.value;
+    select.dispatchEvent(new Event('input', {{ bubbles: true }}));
+    select.dispatchEvent(new Event('change', {{ bubbles: true }}));
+    return true;
+}})(document.querySelector({:?}), {:?})
+"##,
+        css_selector, option
+    );
+    let selected = tab.evaluate(&js, false)?.value;
+    if selected != Some(serde_json::Value::Bool(true)) {
+        return Err(format!("no option {:?}", option).into());
+    }
+    Ok(())
+}
+
+/// Editable list of steps.
+pub fn steps_ui(ui: &mut Ui, id_salt: &str, steps: &mut Vec<Step>) {
+    let mut removed = None;
+    for (index, step) in steps.iter_mut().enumerate() {
+        ui.horizontal(|ui| {
+            ui.label(format!("{}.", index + 1));
+            egui::ComboBox::from_id_salt((id_salt, index))
+                .selected_text(step.action.as_str())
+                .show_ui(ui, |ui| {
+                    for action in StepAction::get_actions() {
+                        ui.selectable_value(&mut step.action, action, action.as_str());
+                    }
+                });
+            if step.action.uses_selector() {
+                let hint = if step.action == StepAction::Scroll {
+                    "css selector, empty for the bottom"
+                } else {
+                    "css selector"
+                };
+                ui.add(
+                    TextEdit::singleline(&mut step.selector)
+                        .hint_text(hint)
+                        .desired_width(160.0),
+                );
+            }
+            if step.action.uses_text() {
+                ui.add(
+                    TextEdit::singleline(&mut step.text)
+                        .hint_text(step.action.text_hint())
+                        .desired_width(120.0),
+                );
+            }
+            if ui.small_button("🗑").clicked() {
+                removed = Some(index);
+            }
+        });
+    }
+    if let Err(error) = validate_steps(steps) {
+        ui.colored_label(egui::Color32::RED, error);
+    }
+    if let Some(index) = removed {
+        steps.remove(index);
+    }
+    if ui.small_button("➕ Add step").clicked() {
+        steps.push(Step {
+            action: StepAction::Click,
+            selector: String::new(),
+            text: String::new(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wait(text: &str) -> Step {
+        Step {
+            action: StepAction::WaitMs,
+            selector: String::new(),
+            text: text.to_owned(),
+        }
+    }
+
+    #[test]
+    fn waits_must_be_short_numbers() {
+        let click = Step {
+            action: StepAction::Click,
+            selector: "#accept".to_owned(),
+            text: String::new(),
+        };
+        assert!(validate_steps(&[]).is_ok());
+        assert!(validate_steps(&[click.clone(), wait(" 500 ")]).is_ok());
+        assert!(validate_steps(&[wait(&MAX_WAIT_MS.to_string())]).is_ok());
+
+        let error = validate_steps(&[click, wait(&(MAX_WAIT_MS + 1).to_string())]).unwrap_err();
+        assert!(error.starts_with("step 2:"), "{}", error);
+        assert!(validate_steps(&[wait("a second")]).is_err());
+        assert!(validate_steps(&[wait("-5")]).is_err());
+    }
+}
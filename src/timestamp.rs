@@ -0,0 +1,182 @@
+use chrono::{DateTime, FixedOffset, Local, NaiveDateTime, TimeZone, Utc};
+
+/// Format `last_updated` was stored in before it became a UTC timestamp.
+const LEGACY_FORMAT: &str = "%b %d %H:%M:%S %Y";
+const DEFAULT_DISPLAY_FORMAT: &str = "%b %d %H:%M:%S %Y";
+
+/// Parse a `last_updated` string saved before timestamps were stored as UTC.
+pub fn parse_legacy(text: &str) -> Option<DateTime<Utc>> {
+    let naive = NaiveDateTime::parse_from_str(text, LEGACY_FORMAT).ok()?;
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|timestamp| timestamp.with_timezone(&Utc))
+}
+
+#[derive(serde::Deserialize, serde::Serialize, PartialEq, Clone, Copy)]
+pub enum DisplayTimezone {
+    Local,
+    Utc,
+    FixedOffset,
+}
+
+impl DisplayTimezone {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DisplayTimezone::Local => "Local",
+            DisplayTimezone::Utc => "UTC",
+            DisplayTimezone::FixedOffset => "Fixed offset",
+        }
+    }
+
+    pub fn get_timezones() -> [DisplayTimezone; 3] {
+        [
+            DisplayTimezone::Local,
+            DisplayTimezone::Utc,
+            DisplayTimezone::FixedOffset,
+        ]
+    }
+}
+
+/// How timestamps are shown in the UI.
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
+#[serde(default)]
+pub struct TimeDisplaySettings {
+    /// chrono strftime format.
+    pub format: String,
+    /// Show "5 min ago" instead of the formatted time.
+    pub relative: bool,
+    pub timezone: DisplayTimezone,
+    /// Used with `DisplayTimezone::FixedOffset`.
+    pub offset_minutes: i32,
+}
+
+impl Default for TimeDisplaySettings {
+    fn default() -> Self {
+        Self {
+            format: DEFAULT_DISPLAY_FORMAT.to_owned(),
+            relative: false,
+            timezone: DisplayTimezone::Local,
+            offset_minutes: 0,
+        }
+    }
+}
+
+impl TimeDisplaySettings {
+    pub fn format(&self, timestamp: &DateTime<Utc>) -> String {
+        if self.relative {
+            format_relative(timestamp, &Utc::now())
+        } else {
+            self.format_absolute(timestamp)
+        }
+    }
+
+    pub fn format_absolute(&self, timestamp: &DateTime<Utc>) -> String {
+        use std::fmt::Write;
+
+        let mut text = String::new();
+        // an invalid user format shouldn't panic the UI
+        let result = match self.timezone {
+            DisplayTimezone::Local => write!(
+                text,
+                "{}",
+                timestamp.with_timezone(&Local).format(&self.format)
+            ),
+            DisplayTimezone::Utc => write!(text, "{}", timestamp.format(&self.format)),
+            DisplayTimezone::FixedOffset => {
+                let offset = FixedOffset::east_opt(self.offset_minutes * 60)
+                    .unwrap_or(FixedOffset::east_opt(0).unwrap());
+                write!(
+                    text,
+                    "{}",
+                    timestamp.with_timezone(&offset).format(&self.format)
+                )
+            }
+        };
+        match result {
+            Ok(()) => text,
+            Err(_) => timestamp.to_rfc3339(),
+        }
+    }
+}
+
+fn format_relative(timestamp: &DateTime<Utc>, now: &DateTime<Utc>) -> String {
+    let seconds = (*now - *timestamp).num_seconds();
+    if seconds < 0 {
+        return "in the future".to_owned();
+    }
+    match seconds {
+        0..=59 => "just now".to_owned(),
+        60..=3599 => format!("{} min ago", seconds / 60),
+        3600..=86399 => format!("{} h ago", seconds / 3600),
+        86400..=172799 => "1 day ago".to_owned(),
+        _ => format!("{} days ago", seconds / 86400),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn legacy_strings_are_local_times() {
+        let parsed = parse_legacy("Oct 01 12:30:00 2024").unwrap();
+        let expected = Local
+            .with_ymd_and_hms(2024, 10, 1, 12, 30, 0)
+            .earliest()
+            .unwrap();
+        assert_eq!(parsed, expected.with_timezone(&Utc));
+    }
+
+    #[test]
+    fn legacy_strings_round_trip() {
+        let written = Local.with_ymd_and_hms(2023, 2, 7, 23, 59, 58).unwrap();
+        let text = crate::format_date_time(&written);
+        assert_eq!(parse_legacy(&text), Some(written.with_timezone(&Utc)));
+    }
+
+    #[test]
+    fn unreadable_legacy_strings_are_none() {
+        for text in [
+            "",
+            "never",
+            "2024-10-01T12:30:00Z",
+            "Oct 32 12:30:00 2024",
+            "Oct 01 25:30:00 2024",
+        ] {
+            assert_eq!(parse_legacy(text), None, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn relative_time_boundaries() {
+        let now = Utc.with_ymd_and_hms(2024, 10, 1, 12, 0, 0).unwrap();
+        let ago = |seconds: i64| format_relative(&(now - Duration::seconds(seconds)), &now);
+        assert_eq!(ago(-1), "in the future");
+        assert_eq!(ago(0), "just now");
+        assert_eq!(ago(59), "just now");
+        assert_eq!(ago(60), "1 min ago");
+        assert_eq!(ago(3599), "59 min ago");
+        assert_eq!(ago(3600), "1 h ago");
+        assert_eq!(ago(86399), "23 h ago");
+        assert_eq!(ago(86400), "1 day ago");
+        assert_eq!(ago(172800), "2 days ago");
+    }
+
+    #[test]
+    fn absolute_time_in_the_chosen_timezone() {
+        let timestamp = Utc.with_ymd_and_hms(2024, 10, 1, 12, 0, 0).unwrap();
+        let mut settings = TimeDisplaySettings {
+            format: "%Y-%m-%d %H:%M".to_owned(),
+            timezone: DisplayTimezone::Utc,
+            ..Default::default()
+        };
+        assert_eq!(settings.format_absolute(&timestamp), "2024-10-01 12:00");
+        settings.timezone = DisplayTimezone::FixedOffset;
+        settings.offset_minutes = -90;
+        assert_eq!(settings.format_absolute(&timestamp), "2024-10-01 10:30");
+        settings.format = "%Q".to_owned();
+        assert_eq!(settings.format_absolute(&timestamp), timestamp.to_rfc3339());
+    }
+}